@@ -0,0 +1,412 @@
+use crate::cover;
+use crate::i18n::Message;
+use crate::live;
+use crate::music;
+use crate::tools;
+use crate::DownloadOptions;
+
+// 動画として扱う出力形式（コンテナ）
+pub const VIDEO_FORMATS: &[&str] = &["mp4", "mkv", "webm", "mov"];
+
+// --cookies-from-browserに渡せるブラウザ
+pub const COOKIE_BROWSERS: &[&str] = &["brave", "chrome", "chromium", "edge", "firefox", "opera", "safari", "vivaldi", "whale"];
+
+// コーデック指定 (設定値, --format-sortでの名前, フォーマットフィルタ用の正規表現)
+struct CodecSpec {
+    name: &'static str,
+    sort_key: &'static str,
+    pattern: &'static str,
+}
+
+const VIDEO_CODECS: &[CodecSpec] = &[
+    CodecSpec { name: "av1", sort_key: "av01", pattern: "^av0?1" },
+    CodecSpec { name: "vp9", sort_key: "vp9", pattern: "^vp0?9" },
+    CodecSpec { name: "hevc", sort_key: "h265", pattern: "^(hev|hvc|h265)" },
+    CodecSpec { name: "h264", sort_key: "h264", pattern: "^(avc|h264)" },
+];
+
+const AUDIO_CODECS: &[CodecSpec] = &[
+    CodecSpec { name: "opus", sort_key: "opus", pattern: "^opus" },
+    CodecSpec { name: "aac", sort_key: "aac", pattern: "^(mp4a|aac)" },
+    CodecSpec { name: "vorbis", sort_key: "vorbis", pattern: "^vorbis" },
+    CodecSpec { name: "mp3", sort_key: "mp3", pattern: "^mp3" },
+    CodecSpec { name: "flac", sort_key: "flac", pattern: "^flac" },
+];
+
+// コンテナごとに格納できるコーデック
+fn container_codecs(container: &str) -> (&'static [&'static str], &'static [&'static str]) {
+    match container {
+        "webm" => (&["av1", "vp9"], &["opus", "vorbis"]),
+        "mov" => (&["hevc", "h264"], &["aac", "mp3"]),
+        "mp4" => (&["av1", "vp9", "hevc", "h264"], &["aac", "opus", "mp3", "flac"]),
+        _ => (&["av1", "vp9", "hevc", "h264"], &["opus", "aac", "vorbis", "mp3", "flac"]),
+    }
+}
+
+// 優先リストの指定が無い場合の音声コーデック（mp4/movは従来通りm4a相当を優先）
+fn default_audio_preference(container: &str) -> Vec<String> {
+    match container {
+        "mp4" | "mov" => vec!["aac".to_string()],
+        "webm" => vec!["opus".to_string()],
+        _ => Vec::new(),
+    }
+}
+
+// 優先リストから未知の値やコンテナに格納できないコーデックを除外する
+fn resolve_codecs(
+    preference: &[String],
+    table: &'static [CodecSpec],
+    allowed: &[&str],
+) -> Vec<&'static CodecSpec> {
+    let mut resolved: Vec<&'static CodecSpec> = Vec::new();
+    for name in preference {
+        let name = name.trim().to_lowercase();
+        if let Some(spec) = table.iter().find(|spec| spec.name == name) {
+            if allowed.contains(&spec.name) && !resolved.iter().any(|s| s.name == spec.name) {
+                resolved.push(spec);
+            }
+        }
+    }
+    resolved
+}
+
+// 映像と音声を結合する場合に、ファイルサイズの上限のうち音声に割り当てる割合 (%)
+const AUDIO_SIZE_SHARE_PERCENT: u64 = 15;
+
+// フレームレート・ビットレートの制約をフォーマットフィルタにする
+// 値が不明なフォーマットは除外しない（?付きの比較）
+fn constraint_filter(options: &DownloadOptions) -> String {
+    let mut filter = String::new();
+    if let Some(fps) = options.max_fps {
+        filter.push_str(&format!("[fps<=?{}]", fps));
+    }
+    if let Some(fps) = options.min_fps {
+        filter.push_str(&format!("[fps>=?{}]", fps));
+    }
+    if let Some(bitrate) = options.max_video_bitrate {
+        filter.push_str(&format!("[vbr<=?{}]", bitrate));
+    }
+    filter
+}
+
+// ファイルサイズの上限のうちshare_percent%をフォーマットフィルタにする
+// フィルタはフォーマットごとに働くため、結合する場合は映像と音声で上限を分ける
+// サイズが不明なフォーマットはビットレートからの推定値(filesize_approx)で比べる
+// yt-dlpのフィルタではKやMの単位が1000倍になるため、単位を付けずにバイト数で書く
+fn size_filter(options: &DownloadOptions, share_percent: u64) -> String {
+    match options.max_filesize_mb {
+        Some(size) => format!("[filesize_approx<=?{}]", size * 1024 * 1024 * share_percent / 100),
+        None => String::new(),
+    }
+}
+
+// 映像フォーマットの選択式と--format-sortの項目を組み立てる
+fn video_format_selection(options: &DownloadOptions) -> (String, Vec<String>) {
+    let (allowed_video, allowed_audio) = container_codecs(&options.format);
+
+    // 互換性重視モードはH.264+AACを強制する従来の動作
+    let video_preference = if options.compatibility_mode {
+        vec!["h264".to_string()]
+    } else {
+        options.video_codec_preference.clone()
+    };
+    let audio_preference = if options.compatibility_mode && options.audio_codec_preference.is_empty() {
+        vec!["aac".to_string()]
+    } else if options.audio_codec_preference.is_empty() {
+        default_audio_preference(&options.format)
+    } else {
+        options.audio_codec_preference.clone()
+    };
+
+    let video_codecs = resolve_codecs(&video_preference, VIDEO_CODECS, allowed_video);
+    let audio_codecs = resolve_codecs(&audio_preference, AUDIO_CODECS, allowed_audio);
+
+    let mut height = if options.quality != "auto" {
+        format!("[height<={}]", options.quality)
+    } else {
+        String::new()
+    };
+    height.push_str(&constraint_filter(options));
+    // 結合する映像と音声はサイズの上限を分け合い、映像と音声が一緒のフォーマットは上限をすべて使う
+    let video_filter = format!("{}{}", height, size_filter(options, 100 - AUDIO_SIZE_SHARE_PERCENT));
+    let audio_filter = size_filter(options, AUDIO_SIZE_SHARE_PERCENT);
+    let combined_filter = format!("{}{}", height, size_filter(options, 100));
+
+    let mut videos: Vec<String> = video_codecs
+        .iter()
+        .map(|spec| format!("bestvideo{}[vcodec~='{}']", video_filter, spec.pattern))
+        .collect();
+    if videos.is_empty() {
+        videos.push(format!("bestvideo{}", video_filter));
+    }
+    let mut audios: Vec<String> = audio_codecs
+        .iter()
+        .map(|spec| format!("bestaudio{}[acodec~='{}']", audio_filter, spec.pattern))
+        .collect();
+    if audios.is_empty() {
+        audios.push(format!("bestaudio{}", audio_filter));
+    }
+
+    // 優先度の高い組み合わせから順に試し、最後はコーデック指定なしにフォールバック
+    let mut selectors = Vec::new();
+    for video in &videos {
+        for audio in &audios {
+            selectors.push(format!("{}+{}", video, audio));
+        }
+    }
+    for spec in &video_codecs {
+        selectors.push(format!("best{}[vcodec~='{}']", combined_filter, spec.pattern));
+    }
+    selectors.push(format!("bestvideo{}+bestaudio{}", video_filter, audio_filter));
+    selectors.push(format!("best{}", combined_filter));
+    selectors.dedup();
+
+    let mut sort = Vec::new();
+    if let Some(spec) = video_codecs.first() {
+        sort.push(format!("vcodec:{}", spec.sort_key));
+    }
+    if let Some(spec) = audio_codecs.first() {
+        sort.push(format!("acodec:{}", spec.sort_key));
+    }
+
+    (selectors.join("/"), sort)
+}
+
+// 音声抽出時のフォーマット選択式（優先コーデックの音声を再エンコードなしで使えるようにする）
+fn audio_format_selection(options: &DownloadOptions) -> (String, Vec<String>) {
+    let (_, allowed_audio) = container_codecs("");
+    let audio_codecs = resolve_codecs(&options.audio_codec_preference, AUDIO_CODECS, allowed_audio);
+
+    let size = size_filter(options, 100);
+
+    let mut selectors: Vec<String> = audio_codecs
+        .iter()
+        .map(|spec| format!("bestaudio{}[acodec~='{}']", size, spec.pattern))
+        .collect();
+    selectors.push(format!("bestaudio{}", size));
+
+    let sort = audio_codecs
+        .first()
+        .map(|spec| vec![format!("acodec:{}", spec.sort_key)])
+        .unwrap_or_default();
+
+    (selectors.join("/"), sort)
+}
+
+// 出力ファイル名のテンプレート
+pub fn output_template(options: &DownloadOptions) -> String {
+    if options.playlist_mode {
+        format!("{}/%(playlist_title)s/%(playlist_index)03d_%(title).100s.%(ext)s", options.output_directory)
+    } else {
+        format!("{}/%(title).100s.%(ext)s", options.output_directory)
+    }
+}
+
+// DownloadOptionsからyt-dlpに渡す引数を組み立てる（最後は -- とURL）
+pub fn build_download_args(options: &DownloadOptions) -> Vec<String> {
+    let mut args: Vec<String> = Vec::new();
+    let mut push = |values: &[&str]| args.extend(values.iter().map(|v| v.to_string()));
+
+    // ライブ録画では割合の代わりに経過時間とサイズを表示する
+    let progress_template = if live::is_recording(options) {
+        live::PROGRESS_TEMPLATE
+    } else {
+        "[DOWNLOADING]:%(progress._percent_str)s"
+    };
+
+    // 基本オプション
+    push(&[
+        "--newline",
+        "--embed-metadata",
+        "--add-metadata",
+        "--default-search", "ytsearch",
+        "--progress-template", progress_template,
+        "--add-header", "Accept-Language:ja-JP",
+        "--extractor-args", "youtube:lang=ja",
+        "--no-warnings",
+        "--encoding", "utf-8",
+    ]);
+
+    // 検出したffmpegを使わせる
+    if let Some(ffmpeg) = tools::ffmpeg_location() {
+        push(&["--ffmpeg-location", &ffmpeg]);
+    }
+
+    // Cookie設定
+    match options.cookie_source.as_str() {
+        "file" => {
+            if let Some(cookie_path) = &options.cookie_file_path {
+                push(&["--cookies", cookie_path]);
+            }
+        }
+        browser if COOKIE_BROWSERS.contains(&browser) => {
+            push(&["--cookies-from-browser", browser]);
+        }
+        _ => {}
+    }
+
+    // ライブ録画
+    let live_args = live::live_args(options);
+    push(&live_args.iter().map(String::as_str).collect::<Vec<_>>());
+
+    // 出力形式と品質設定
+    let mut sort = Vec::new();
+    if VIDEO_FORMATS.contains(&options.format.as_str()) {
+        let (selector, codec_sort) = video_format_selection(options);
+        push(&["--merge-output-format", &options.format]);
+        push(&["-f", &selector]);
+        sort.extend(codec_sort);
+    } else {
+        let (selector, codec_sort) = audio_format_selection(options);
+        push(&["-f", &selector, "-x", "--audio-format", &options.format]);
+        if options.format == "mp3" && options.quality != "auto" {
+            push(&["--audio-quality", &options.quality]);
+        } else {
+            push(&["--audio-quality", "0"]);
+        }
+        sort.extend(codec_sort);
+
+        // ミュージックモード
+        if options.music_mode {
+            let rule_args = music::rule_args(&music::effective_rules(&options.metadata_rules));
+            push(&rule_args.iter().map(String::as_str).collect::<Vec<_>>());
+        }
+    }
+
+    // HDR・フレームレート優先の並び順
+    let mut leading = Vec::new();
+    if options.hdr_mode {
+        leading.push("hdr".to_string());
+    }
+    if options.prefer_60fps {
+        leading.push("fps:60".to_string());
+    }
+    if options.hdr_mode {
+        leading.push("res".to_string());
+        if sort.is_empty() {
+            sort.push("codec".to_string());
+        }
+        sort.push("ext".to_string());
+        sort.push("size".to_string());
+    }
+    sort.splice(0..0, leading);
+    if !sort.is_empty() {
+        push(&["--format-sort", &sort.join(",")]);
+    }
+
+    // 中断されたジョブは途中ファイルから続ける
+    if options.resume {
+        push(&["--continue"]);
+    }
+
+    // yt-dlp内部での再試行回数
+    if let Some(retries) = options.retries {
+        push(&["--retries", &retries.to_string()]);
+    }
+    if let Some(retries) = options.fragment_retries {
+        push(&["--fragment-retries", &retries.to_string()]);
+    }
+
+    // 追加オプション
+    if options.chapter_embed {
+        push(&["--embed-chapters", "--add-chapters"]);
+    }
+
+    if !options.concurrent_connections.is_empty() && options.concurrent_connections != "0" {
+        push(&["-N", &options.concurrent_connections]);
+    }
+
+    // 出力パス設定
+    push(&["-o", &output_template(options)]);
+
+    // サムネイル設定
+    let is_video = VIDEO_FORMATS.contains(&options.format.as_str());
+    let cover_settings = cover::CoverSettings::from_options(options);
+    if options.thumbnail_embed && is_video {
+        let format = if cover_settings.format == "png" { "png" } else { "jpg" };
+        push(&["--embed-thumbnail", "--convert-thumbnails", format]);
+        if let Some(ppa) = cover::embed_postprocessor_args(&cover_settings) {
+            push(&["--ppa", &ppa]);
+        }
+    }
+    // 音声形式への埋め込みとcover.*の保存はダウンロード後にcoverモジュールで行う
+    if cover::uses_pipeline(options) {
+        push(&["--write-thumbnail", "--no-write-playlist-metafiles"]);
+        if !(options.thumbnail_embed && is_video) {
+            push(&["--convert-thumbnails", "png"]);
+        }
+    }
+
+    // 「-」で始まるURLがオプションとして解釈されないよう区切りの後に置く
+    push(&["--", &options.url]);
+    args
+}
+
+// 品質に関する制約を人が読める形にまとめる（コマンドプレビュー用）
+pub fn describe_constraints(options: &DownloadOptions) -> Vec<String> {
+    let mut lines = Vec::new();
+    let is_video = VIDEO_FORMATS.contains(&options.format.as_str());
+    if is_video {
+        if options.quality != "auto" {
+            lines.push(Message::new("constraint.max_height").arg("height", &options.quality).text());
+        }
+        if let Some(fps) = options.max_fps {
+            lines.push(Message::new("constraint.max_fps").arg("fps", fps).text());
+        }
+        if let Some(fps) = options.min_fps {
+            lines.push(Message::new("constraint.min_fps").arg("fps", fps).text());
+        }
+        if options.prefer_60fps {
+            lines.push(Message::new("constraint.prefer_60fps").text());
+        }
+        if let Some(bitrate) = options.max_video_bitrate {
+            lines.push(Message::new("constraint.max_video_bitrate").arg("bitrate", bitrate).text());
+        }
+    }
+    if let Some(size) = options.max_filesize_mb {
+        lines.push(Message::new("constraint.max_filesize").arg("size", size).text());
+    }
+    lines
+}
+
+// プレビュー表示用に引数をシェル風にクォートする
+pub fn quote_arg(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_./:=,%@+".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{default_settings, to_snake_case};
+    use serde_json::Value;
+
+    fn options_with_max_filesize(size: u64) -> DownloadOptions {
+        let mut settings = default_settings();
+        settings["url"] = Value::from("https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        settings["maxFilesizeMb"] = Value::from(size);
+        serde_json::from_value(to_snake_case(settings)).expect("settings should deserialize")
+    }
+
+    // 上限はMB単位（1024×1024バイト）で、単位の解釈がぶれないようにバイト数で渡すこと
+    #[test]
+    fn size_filter_is_written_in_bytes() {
+        let options = options_with_max_filesize(100);
+        assert_eq!(size_filter(&options, 100), "[filesize_approx<=?104857600]");
+        assert_eq!(size_filter(&options, 15), "[filesize_approx<=?15728640]");
+    }
+
+    #[test]
+    fn size_filter_is_empty_without_limit() {
+        let mut options = options_with_max_filesize(100);
+        options.max_filesize_mb = None;
+        assert_eq!(size_filter(&options, 100), "");
+    }
+}
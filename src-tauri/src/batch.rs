@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Map;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+use crate::i18n::Message;
+use crate::presets;
+use crate::queue;
+use crate::settings;
+use crate::urls;
+
+// 読み込むファイルの上限
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+// 一度に取り込むURLの上限
+const MAX_URLS: usize = 1000;
+// タイトルを同時に取得する数
+const PROBE_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Deserialize)]
+pub struct BatchImportRequest {
+    // 貼り付けた文章、または.txt/.csvファイルのパス（両方指定した場合は両方から取り出す）
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub file_path: Option<String>,
+    // プリセット名（省略すると既定のプリセット）
+    #[serde(default)]
+    pub preset: Option<String>,
+    // yt-dlpでタイトルを取得する
+    #[serde(default)]
+    pub probe_titles: bool,
+    // falseの場合は取り出した結果を返すだけでキューには追加しない
+    #[serde(default)]
+    pub enqueue: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BatchItem {
+    pub url: String,
+    pub kind: urls::UrlKind,
+    // 動画とプレイリストの両方を指している（プリセットのplaylist_modeで選ばれる）
+    pub ambiguous: bool,
+    pub title: Option<String>,
+    pub job: Option<queue::JobInfo>,
+    pub error: Option<Message>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchImportResult {
+    pub items: Vec<BatchItem>,
+    // 重複として除いた数
+    pub duplicates: usize,
+    // 上限を超えたため取り込まなかった数
+    pub skipped: usize,
+}
+
+fn read_file(path: &str) -> Result<String, Message> {
+    let path = Path::new(path);
+    let size = std::fs::metadata(path)
+        .map_err(|e| Message::new("batch.read_failed").arg("path", path.display()).arg("error", e))?
+        .len();
+    if size > MAX_FILE_BYTES {
+        return Err(Message::new("batch.file_too_large").arg("path", path.display()));
+    }
+    let bytes = std::fs::read(path).map_err(|e| Message::new("batch.read_failed").arg("path", path.display()).arg("error", e))?;
+    let text = String::from_utf8_lossy(&bytes);
+    // Excelなどが付けるBOMを取り除く
+    Ok(text.trim_start_matches('\u{feff}').to_string())
+}
+
+// URLだけが書かれた行やCSVのセルは対応サイト以外のURLも取り込む（文章の中のURLは対応サイトのみ）
+// URLには,や;が含まれることがあるため、文章や.txtの行は区切らない
+fn cell_urls(cell: &str) -> Vec<String> {
+    let cell = cell.trim();
+    match urls::find_urls(cell).as_slice() {
+        [only] if *only == cell => {
+            vec![urls::classify(cell).map(|classified| classified.normalized).unwrap_or_else(|_| cell.to_string())]
+        }
+        _ => urls::extract_media_urls(cell),
+    }
+}
+
+// 1行目で最も多い区切り文字（引用符の中は数えない）
+fn csv_delimiter(text: &str) -> char {
+    let first = text.lines().next().unwrap_or_default();
+    let mut quoted = false;
+    let mut counts = [(',', 0), (';', 0), ('\t', 0)];
+    for c in first.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if !quoted {
+            if let Some((_, count)) = counts.iter_mut().find(|(delimiter, _)| *delimiter == c) {
+                *count += 1;
+            }
+        }
+    }
+    counts.iter().max_by_key(|(_, count)| *count).filter(|(_, count)| *count > 0).map(|(delimiter, _)| *delimiter).unwrap_or(',')
+}
+
+// CSVをセルに分ける（"で囲んだセルの区切り文字・改行と、""による"を扱う）
+fn csv_cells(text: &str) -> Vec<String> {
+    let delimiter = csv_delimiter(text);
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            '\r' | '\n' if !quoted => cells.push(std::mem::take(&mut cell)),
+            c if c == delimiter && !quoted => cells.push(std::mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell);
+    cells
+}
+
+// 正規化したURLをfoundに追加し、既にあったものの数を返す
+fn append_unique(found: &mut Vec<String>, urls: impl IntoIterator<Item = String>) -> usize {
+    let mut duplicates = 0;
+    for url in urls {
+        if found.contains(&url) {
+            duplicates += 1;
+        } else {
+            found.push(url);
+        }
+    }
+    duplicates
+}
+
+// URLを取り出し、正規化して重複を除く（戻り値はURLと重複の数）
+// csvがtrueならセルごとに、それ以外（文章や.txt）は行ごとに探す
+pub fn extract(text: &str, csv: bool) -> (Vec<String>, usize) {
+    let urls: Vec<String> = if csv {
+        csv_cells(text).iter().flat_map(|cell| cell_urls(cell)).collect()
+    } else {
+        text.lines().flat_map(cell_urls).collect()
+    };
+    let mut found = Vec::new();
+    let duplicates = append_unique(&mut found, urls);
+    (found, duplicates)
+}
+
+fn is_csv(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case("csv"))
+}
+
+fn item(url: String) -> BatchItem {
+    let (kind, ambiguous) = urls::classify(&url)
+        .map(|classified| (classified.kind, classified.ambiguous))
+        .unwrap_or((urls::UrlKind::Other, false));
+    BatchItem {
+        url,
+        kind,
+        ambiguous,
+        title: None,
+        job: None,
+        error: None,
+    }
+}
+
+// タイトルを並行して取得する（取得できなかったURLはerrorに理由を入れる）
+async fn probe_titles(items: &mut [BatchItem]) {
+    let semaphore = Arc::new(Semaphore::new(PROBE_CONCURRENCY));
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, item) in items.iter().enumerate() {
+        let semaphore = semaphore.clone();
+        let url = item.url.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            (index, crate::get_title_from_url(url).await)
+        });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, Ok(title))) => items[index].title = Some(title),
+            Ok((index, Err(e))) => items[index].error = Some(e),
+            Err(_) => {}
+        }
+    }
+}
+
+pub async fn import(request: BatchImportRequest) -> Result<BatchImportResult, Message> {
+    let (mut found, mut duplicates) = extract(&request.text.unwrap_or_default(), false);
+    if let Some(path) = request.file_path.as_deref().filter(|path| !path.trim().is_empty()) {
+        let (file_urls, file_duplicates) = extract(&read_file(path)?, is_csv(path));
+        duplicates += file_duplicates + append_unique(&mut found, file_urls);
+    }
+
+    // 存在しないプリセットは取り出す前に知らせる
+    let preset = request.preset.as_deref().filter(|preset| !preset.trim().is_empty());
+    presets::resolve(preset)?;
+
+    if found.is_empty() {
+        return Err(Message::new("batch.no_urls"));
+    }
+    let skipped = found.len().saturating_sub(MAX_URLS);
+    found.truncate(MAX_URLS);
+
+    let mut items: Vec<BatchItem> = found.into_iter().map(item).collect();
+    if request.probe_titles {
+        probe_titles(&mut items).await;
+    }
+
+    if request.enqueue {
+        // タイトルを取得できなかったURLも追加する（yt-dlpのエラーはジョブの結果として表示する）
+        for item in &mut items {
+            match settings::options_for(&item.url, preset, &Map::new()).await {
+                Ok(options) => item.job = Some(queue::enqueue(options, "batch")),
+                Err(e) => item.error = Some(e),
+            }
+        }
+    }
+    Ok(BatchImportResult { items, duplicates, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_and_removes_duplicates() {
+        let text = "https://youtu.be/dQw4w9WgXcQ?si=a\nhttps://www.youtube.com/watch?v=dQw4w9WgXcQ&feature=share\n\
+                    見て→https://nicovideo.jp/watch/sm9 とブログ https://blog.example.com/post\nhttps://example.org/video/1";
+        let (urls, duplicates) = extract(text, false);
+        assert_eq!(
+            urls,
+            vec!["https://www.youtube.com/watch?v=dQw4w9WgXcQ", "https://nicovideo.jp/watch/sm9", "https://example.org/video/1"]
+        );
+        assert_eq!(duplicates, 1);
+    }
+
+    #[test]
+    fn keeps_separators_inside_text_urls() {
+        let (urls, _) = extract("https://example.org/items;id=1,2\nhttps://example.org/search?q=a,b, https://vimeo.com/123456", false);
+        assert_eq!(urls, vec!["https://example.org/items;id=1,2", "https://vimeo.com/123456"]);
+    }
+
+    #[test]
+    fn reads_quoted_csv_cells() {
+        let csv = "title,url\n\"Song, live\",\"https://www.youtube.com/watch?v=dQw4w9WgXcQ&si=a\"\n\"say \"\"hi\"\"\",https://example.org/a,b\n";
+        let (urls, duplicates) = extract(csv, true);
+        assert_eq!(urls, vec!["https://www.youtube.com/watch?v=dQw4w9WgXcQ", "https://example.org/a"]);
+        assert_eq!(duplicates, 0);
+
+        let (urls, _) = extract("\"https://example.org/items;id=1,2\",memo\n", true);
+        assert_eq!(urls, vec!["https://example.org/items;id=1,2"]);
+    }
+
+    #[test]
+    fn detects_csv_delimiter() {
+        assert_eq!(csv_delimiter("title;url\na;b"), ';');
+        assert_eq!(csv_delimiter("title\turl"), '\t');
+        assert_eq!(csv_delimiter("\"a;b;c\",url"), ',');
+        assert_eq!(csv_delimiter("https://example.org/"), ',');
+        let (urls, _) = extract("title\turl\nSong\thttps://youtu.be/dQw4w9WgXcQ\n", true);
+        assert_eq!(urls, vec!["https://www.youtube.com/watch?v=dQw4w9WgXcQ"]);
+    }
+
+    #[test]
+    fn strips_bom_from_files() {
+        let dir = std::env::temp_dir().join(format!("necd-batch-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("urls.csv");
+        std::fs::write(&path, "\u{feff}https://youtu.be/dQw4w9WgXcQ,memo\r\n").unwrap();
+        let content = read_file(&path.display().to_string()).unwrap();
+        assert!(is_csv(&path.display().to_string()));
+        let (urls, _) = extract(&content, true);
+        assert_eq!(urls, vec!["https://www.youtube.com/watch?v=dQw4w9WgXcQ"]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
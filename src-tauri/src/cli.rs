@@ -0,0 +1,334 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::args;
+use crate::events::EventSink;
+use crate::i18n::Message;
+use crate::settings;
+use crate::DownloadProgress;
+
+// 終了コード
+const EXIT_OK: i32 = 0;
+// ダウンロードまたは後処理に失敗した
+const EXIT_FAILED: i32 = 1;
+// 引数の誤り
+const EXIT_USAGE: i32 = 2;
+// 保存先の確認で中止した（フォルダを作成できない・空き容量不足など）
+const EXIT_PREFLIGHT: i32 = 3;
+// 同名ファイルがあり、collisionPolicyが"ask"のため中止した
+const EXIT_NEEDS_CONFIRMATION: i32 = 4;
+// yt-dlpを実行できないなど、ダウンロードを開始できなかった
+const EXIT_SETUP: i32 = 5;
+
+// 値を取るオプションと対応するDownloadOptionsの項目
+const VALUE_OPTIONS: &[(&[&str], &str)] = &[
+    (&["-f", "--format"], "format"),
+    (&["-q", "--quality"], "quality"),
+    (&["-o", "--out"], "output_directory"),
+    (&["--connections"], "concurrent_connections"),
+    (&["--collision"], "collision_policy"),
+    (&["--reencode"], "reencode_preset"),
+    (&["--loudness"], "loudness_mode"),
+    (&["--live"], "live_mode"),
+    (&["--live-max-duration"], "live_max_duration"),
+    (&["--retries"], "retries"),
+    (&["--max-attempts"], "max_attempts"),
+];
+
+// 値を取らないオプションと、指定した場合に設定する値
+const FLAG_OPTIONS: &[(&str, &str, bool)] = &[
+    ("--playlist", "playlist_mode", true),
+    ("--no-playlist", "playlist_mode", false),
+    ("--embed-thumbnail", "thumbnail_embed", true),
+    ("--embed-chapters", "chapter_embed", true),
+    ("--music", "music_mode", true),
+];
+
+struct DownloadArgs {
+    urls: Vec<String>,
+    // プリセット名（省略すると既定のプリセット）
+    preset: Option<String>,
+    // DownloadOptionsの項目名（snake_case）と値
+    overrides: Map<String, Value>,
+    quiet: bool,
+    json: bool,
+}
+
+// 端末に進捗を表示する
+#[derive(Clone)]
+struct Terminal {
+    quiet: bool,
+    // 標準エラーが端末の場合のみ進捗バーを1行で書き換える
+    interactive: bool,
+    // 進捗バーの行が改行されずに残っている
+    line_open: Arc<AtomicBool>,
+}
+
+impl Terminal {
+    fn status(&self, text: &str) {
+        if self.interactive && !self.quiet {
+            eprint!("\r\x1b[2K{}", text);
+            let _ = std::io::stderr().flush();
+            self.line_open.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn line(&self, text: &str, is_error: bool) {
+        if self.quiet && !is_error {
+            return;
+        }
+        if self.line_open.swap(false, Ordering::SeqCst) {
+            eprintln!();
+        }
+        eprintln!("{}", text);
+    }
+}
+
+fn progress_bar(percent: f64) -> String {
+    let filled = (percent.clamp(0.0, 100.0) / 5.0).round() as usize;
+    format!("[{}{}] {:5.1}%", "#".repeat(filled), "-".repeat(20 - filled), percent)
+}
+
+impl EventSink for Terminal {
+    fn send<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if !matches!(event, "download-log" | "postprocess-progress") {
+            return;
+        }
+        let Ok(value) = serde_json::to_value(payload) else {
+            return;
+        };
+        let message = value.get("message").and_then(Value::as_str).unwrap_or_default();
+        let is_error = value.get("is_error").and_then(Value::as_bool).unwrap_or(false);
+        let progress = value.get("progress").and_then(Value::as_f64);
+        let key = value.pointer("/i18n/key").and_then(Value::as_str);
+
+        if key == Some("recording.progress") {
+            self.status(message);
+        } else if message.contains("[DOWNLOADING]:") || message.contains("[ENCODING]:") {
+            if let Some(percent) = progress {
+                self.status(&progress_bar(percent));
+            }
+        } else {
+            self.line(message, is_error);
+        }
+    }
+}
+
+fn usage() -> String {
+    Message::new("cli.usage").text()
+}
+
+// 数値や真偽値、JSONとして読めない値は文字列として扱う
+fn parse_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+// -qの値をyt-dlpに渡す形にそろえる（動画は"1080p"を"1080"に、音声は"320"を"320k"にする）
+fn normalize_quality(format: &str, raw: &str) -> Result<String, String> {
+    let value = raw.trim().to_lowercase();
+    if value == "auto" {
+        return Ok(value);
+    }
+    let is_video = args::VIDEO_FORMATS.contains(&format);
+    let number = if is_video {
+        value.strip_suffix('p').unwrap_or(&value)
+    } else {
+        value.strip_suffix('k').unwrap_or(&value)
+    };
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) || number.starts_with('0') {
+        return Err(Message::new("cli.invalid_quality").arg("format", format).arg("value", raw).text());
+    }
+    Ok(if is_video { number.to_string() } else { format!("{}k", number) })
+}
+
+fn parse_download_args(args: &[String]) -> Result<DownloadArgs, String> {
+    let mut parsed = DownloadArgs {
+        urls: Vec::new(),
+        preset: None,
+        overrides: Map::new(),
+        quiet: false,
+        json: false,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let arg = arg.as_str();
+        let mut value_for = |option: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| Message::new("cli.missing_value").arg("option", option).text())
+        };
+
+        if let Some((_, field)) = VALUE_OPTIONS.iter().find(|(names, _)| names.contains(&arg)) {
+            let value = value_for(arg)?;
+            parsed.overrides.insert(field.to_string(), parse_value(&value));
+        } else if let Some((_, field, flag)) = FLAG_OPTIONS.iter().find(|(name, _, _)| *name == arg) {
+            parsed.overrides.insert(field.to_string(), Value::Bool(*flag));
+        } else {
+            match arg {
+                "--cookies" => {
+                    let path = value_for(arg)?;
+                    parsed.overrides.insert("cookie_source".to_string(), Value::String("file".to_string()));
+                    parsed.overrides.insert("cookie_file_path".to_string(), Value::String(path));
+                }
+                "--preset" => parsed.preset = Some(value_for(arg)?),
+                "--cookies-from-browser" => {
+                    let browser = value_for(arg)?.trim().to_lowercase();
+                    if !args::COOKIE_BROWSERS.contains(&browser.as_str()) {
+                        return Err(Message::new("cli.invalid_browser")
+                            .arg("browser", &browser)
+                            .arg("supported", args::COOKIE_BROWSERS.join(", "))
+                            .text());
+                    }
+                    parsed.overrides.insert("cookie_source".to_string(), Value::String(browser));
+                }
+                // 任意の項目を設定ファイルと同じ名前で指定する（例: --set maxFps=30）
+                "--set" => {
+                    let assignment = value_for(arg)?;
+                    let (key, value) = assignment
+                        .split_once('=')
+                        .ok_or_else(|| Message::new("cli.invalid_set").arg("value", &assignment).text())?;
+                    if let Value::Object(map) = settings::to_snake_case(serde_json::json!({ key.trim(): parse_value(value.trim()) })) {
+                        parsed.overrides.extend(map);
+                    }
+                }
+                "--quiet" => parsed.quiet = true,
+                "--json" => parsed.json = true,
+                _ if arg.starts_with('-') => {
+                    return Err(Message::new("cli.unknown_option").arg("option", arg).text());
+                }
+                _ => parsed.urls.push(arg.to_string()),
+            }
+        }
+    }
+    if parsed.urls.is_empty() {
+        return Err(Message::new("cli.missing_url").text());
+    }
+    Ok(parsed)
+}
+
+fn exit_code(result: &DownloadProgress) -> i32 {
+    if result.preflight_error.is_some() {
+        EXIT_PREFLIGHT
+    } else if result.collision.as_ref().is_some_and(|collision| collision.action == "ask") {
+        EXIT_NEEDS_CONFIRMATION
+    } else if result.is_error {
+        EXIT_FAILED
+    } else {
+        EXIT_OK
+    }
+}
+
+async fn download(args: DownloadArgs) -> i32 {
+    let terminal = Terminal {
+        quiet: args.quiet,
+        interactive: std::io::stderr().is_terminal(),
+        line_open: Arc::new(AtomicBool::new(false)),
+    };
+
+    // 複数のURLは順に処理し、最初に失敗した終了コードを返す
+    let mut code = EXIT_OK;
+    for url in &args.urls {
+        let options = match settings::options_for(url, args.preset.as_deref(), &args.overrides).await.and_then(|mut options| {
+            // 形式はプリセットや設定で決まることがあるため、-qは形式が決まってから確かめる
+            if args.overrides.contains_key("quality") {
+                options.quality = normalize_quality(&options.format, &options.quality)?;
+            }
+            Ok(options)
+        }) {
+            Ok(options) => options,
+            Err(e) => {
+                terminal.line(&e.text(), true);
+                return EXIT_USAGE;
+            }
+        };
+        let result_code = match crate::download(&terminal, options).await {
+            Ok(result) => {
+                terminal.line(&result.message, result.is_error);
+                if args.json {
+                    println!("{}", serde_json::to_string(&result).unwrap_or_default());
+                } else {
+                    for file in &result.files {
+                        println!("{}", file);
+                    }
+                }
+                exit_code(&result)
+            }
+            Err(e) => {
+                terminal.line(&Message::new("common.error").arg("detail", e).text(), true);
+                EXIT_SETUP
+            }
+        };
+        if code == EXIT_OK {
+            code = result_code;
+        }
+    }
+    code
+}
+
+#[cfg(target_os = "windows")]
+fn attach_console() {
+    // リリースビルドはwindows_subsystem = "windows"のため、起動元のコンソールに出力をつなぐ
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+// サブコマンドが指定されていればウィンドウを開かずに実行し、終了コードを返す（それ以外はNone）
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args.first()?.as_str();
+    if !matches!(command, "download" | "help" | "--help" | "-h" | "--version" | "-V") {
+        return None;
+    }
+    #[cfg(target_os = "windows")]
+    attach_console();
+
+    Some(match command {
+        "download" => match parse_download_args(&args[1..]) {
+            Ok(download_args) => tauri::async_runtime::block_on(download(download_args)),
+            Err(e) => {
+                eprintln!("{}\n\n{}", e, usage());
+                EXIT_USAGE
+            }
+        },
+        "--version" | "-V" => {
+            println!("necd-tauri {}", env!("CARGO_PKG_VERSION"));
+            EXIT_OK
+        }
+        _ => {
+            println!("{}", usage());
+            EXIT_OK
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_quality() {
+        assert_eq!(normalize_quality("mp4", "1080p").unwrap(), "1080");
+        assert_eq!(normalize_quality("mkv", "720").unwrap(), "720");
+        assert_eq!(normalize_quality("mp3", "320").unwrap(), "320k");
+        assert_eq!(normalize_quality("mp3", "192K").unwrap(), "192k");
+        assert_eq!(normalize_quality("mp3", "Auto").unwrap(), "auto");
+        assert!(normalize_quality("mp4", "320k").is_err());
+        assert!(normalize_quality("mp3", "1080p").is_err());
+        assert!(normalize_quality("mp4", "best").is_err());
+    }
+
+    #[test]
+    fn accepts_only_supported_cookie_browsers() {
+        let args = |browser: &str| ["https://example.com/v", "--cookies-from-browser", browser].map(String::from);
+        let parsed = parse_download_args(&args("Chrome")).unwrap();
+        assert_eq!(parsed.overrides.get("cookie_source"), Some(&Value::String("chrome".to_string())));
+        assert!(parse_download_args(&args("netscape")).is_err());
+    }
+}
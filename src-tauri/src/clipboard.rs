@@ -0,0 +1,132 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::events::EventSink;
+use crate::i18n::Message;
+use crate::queue;
+use crate::settings;
+use crate::urls;
+
+// 監視間隔の既定値と下限（設定のclipboardPollIntervalMs）
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+const MIN_INTERVAL_MS: u64 = 250;
+// 検出済みとして覚えておくURLの数
+const MAX_SEEN: usize = 200;
+
+// "url-detected"イベントの内容
+#[derive(Debug, Serialize, Clone)]
+pub struct DetectedUrl {
+    pub url: String,
+    // 自動で追加した場合のジョブ
+    pub job: Option<queue::JobInfo>,
+    pub error: Option<Message>,
+}
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+// 設定を変更するたびに増やし、古い監視を終了させる
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static SEEN: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+#[cfg(target_os = "linux")]
+fn read_wayland() -> Option<Result<String, Message>> {
+    use std::process::Command;
+    std::env::var_os("WAYLAND_DISPLAY")?;
+    match Command::new("wl-paste").args(["--no-newline", "--type", "text"]).output() {
+        Ok(output) if output.status.success() => Some(Ok(String::from_utf8_lossy(&output.stdout).to_string())),
+        // クリップボードが空の場合も失敗になる
+        Ok(_) => Some(Ok(String::new())),
+        // wl-pasteが無い場合はXWayland経由で読む
+        Err(_) => None,
+    }
+}
+
+// クリップボードの文字列を読む（Waylandではwl-paste、それ以外はOSのAPI）
+pub fn read_text() -> Result<String, Message> {
+    #[cfg(target_os = "linux")]
+    if let Some(result) = read_wayland() {
+        return result;
+    }
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| Message::new("clipboard.open_failed").arg("error", e))?;
+    match clipboard.get_text() {
+        Ok(text) => Ok(text),
+        // 画像など文字列以外の場合
+        Err(arboard::Error::ContentNotAvailable) => Ok(String::new()),
+        Err(e) => Err(Message::new("clipboard.read_failed").arg("error", e)),
+    }
+}
+
+// 未検出のURLなら記録してtrueを返す
+fn mark_seen(url: &str) -> bool {
+    let mut seen = SEEN.lock().unwrap_or_else(|e| e.into_inner());
+    if seen.iter().any(|existing| existing == url) {
+        return false;
+    }
+    seen.push_back(url.to_string());
+    while seen.len() > MAX_SEEN {
+        seen.pop_front();
+    }
+    true
+}
+
+async fn detected(app: &AppHandle, url: String, enqueue: bool) {
+    let mut result = DetectedUrl { url, job: None, error: None };
+    if enqueue {
+        match settings::options_for(&result.url, None, &Map::new()).await {
+            Ok(options) => result.job = Some(queue::enqueue(options, "clipboard")),
+            Err(e) => result.error = Some(e),
+        }
+    }
+    app.send("url-detected", result);
+}
+
+async fn watch(app: AppHandle, generation: u64, enqueue: bool, interval: Duration) {
+    // 監視を始めた時点の内容は対象にしない
+    let mut last = tokio::task::spawn_blocking(read_text).await.ok().and_then(Result::ok);
+    while GENERATION.load(Ordering::SeqCst) == generation {
+        tokio::time::sleep(interval).await;
+        let Ok(Ok(text)) = tokio::task::spawn_blocking(read_text).await else {
+            continue;
+        };
+        if last.as_deref() == Some(text.as_str()) {
+            continue;
+        }
+        for url in urls::extract_media_urls(&text) {
+            if GENERATION.load(Ordering::SeqCst) == generation && mark_seen(&url) {
+                detected(&app, url, enqueue).await;
+            }
+        }
+        last = Some(text);
+    }
+}
+
+// 設定のclipboardWatch（"off", "notify", "enqueue"）に合わせて監視を開始・停止する
+pub fn apply_settings() {
+    let Some(app) = APP.get().cloned() else {
+        return;
+    };
+    let settings = settings::saved_settings();
+    let mode = settings.get("clipboardWatch").and_then(Value::as_str).unwrap_or("off").to_string();
+    let interval = settings
+        .get("clipboardPollIntervalMs")
+        .and_then(Value::as_u64)
+        .unwrap_or(DEFAULT_INTERVAL_MS)
+        .max(MIN_INTERVAL_MS);
+
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let enqueue = match mode.as_str() {
+        "notify" => false,
+        "enqueue" => true,
+        _ => return,
+    };
+    tauri::async_runtime::spawn(watch(app, generation, enqueue, Duration::from_millis(interval)));
+}
+
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+    apply_settings();
+}
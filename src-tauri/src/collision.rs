@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::args;
+use crate::preflight::ProbedItem;
+use crate::DownloadOptions;
+
+// 連番を探す上限
+const MAX_RENAME_SUFFIX: u32 = 999;
+
+// 同名ファイルがあった場合にどの方針で何をしたか
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CollisionReport {
+    // "skip", "overwrite", "rename", "ask"
+    pub policy: String,
+    // "none"（衝突なし）, "skipped", "partially_skipped", "overwritten", "renamed", "ask"（確認待ち）, "unchecked"（ファイル名を予測できなかった）
+    pub action: String,
+    // 既に存在していたファイル
+    pub existing: Vec<String>,
+    // renameで付けた連番 (例: " (1)")
+    pub suffix: Option<String>,
+}
+
+pub struct Resolution {
+    // yt-dlpに追加する引数
+    pub args: Vec<String>,
+    // falseならダウンロードを開始しない
+    pub proceed: bool,
+    pub report: CollisionReport,
+}
+
+// 未指定の場合はyt-dlpの既定と同じくスキップする
+fn policy(options: &DownloadOptions) -> &str {
+    match options.collision_policy.as_str() {
+        "overwrite" | "rename" | "ask" => options.collision_policy.as_str(),
+        _ => "skip",
+    }
+}
+
+// 後処理後のファイル名（音声抽出は拡張子が変わる）
+fn final_path(options: &DownloadOptions, filename: &Path) -> PathBuf {
+    if args::VIDEO_FORMATS.contains(&options.format.as_str()) {
+        filename.to_path_buf()
+    } else {
+        filename.with_extension(&options.format)
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}{}.{}", stem, suffix, ext.to_string_lossy()),
+        None => format!("{}{}", stem, suffix),
+    };
+    path.with_file_name(name)
+}
+
+// 上書きの引数（--force-overwritesは--no-continueを含むため、中断したジョブの再開時は途中ファイルを消さないよう付けない）
+fn overwrite_args(options: &DownloadOptions) -> Vec<String> {
+    if options.resume {
+        Vec::new()
+    } else {
+        vec!["--force-overwrites".to_string()]
+    }
+}
+
+// 自動再試行の引数（既存ファイルの上書きは初回で済んでいるため、途中まで取得したファイルから続ける）
+pub fn retry_args(args: &[String]) -> Vec<String> {
+    args.iter().filter(|arg| *arg != "--force-overwrites").cloned().collect()
+}
+
+// 予測したファイル名をもとに同名ファイルの扱いを決める
+pub fn resolve(options: &DownloadOptions, probed: Option<&[ProbedItem]>) -> Resolution {
+    let policy = policy(options).to_string();
+    let report = |action: &str, existing: Vec<String>, suffix: Option<String>| CollisionReport {
+        policy: policy.clone(),
+        action: action.to_string(),
+        existing,
+        suffix,
+    };
+
+    let Some(items) = probed else {
+        // 予測できない場合はyt-dlpの判定に任せる
+        let args = match policy.as_str() {
+            "overwrite" => overwrite_args(options),
+            _ => vec!["--no-overwrites".to_string()],
+        };
+        return Resolution { args, proceed: true, report: report("unchecked", Vec::new(), None) };
+    };
+
+    let finals: Vec<PathBuf> = items.iter().map(|item| final_path(options, &item.filename)).collect();
+    let existing: Vec<String> = finals
+        .iter()
+        .filter(|path| path.exists())
+        .map(|path| path.display().to_string())
+        .collect();
+    if existing.is_empty() {
+        return Resolution { args: Vec::new(), proceed: true, report: report("none", existing, None) };
+    }
+
+    match policy.as_str() {
+        "overwrite" => Resolution {
+            args: overwrite_args(options),
+            proceed: true,
+            report: report("overwritten", existing, None),
+        },
+        "rename" => {
+            // 出力する全ファイルに同じ連番を付ける
+            let suffix = (1..=MAX_RENAME_SUFFIX)
+                .map(|n| format!(" ({})", n))
+                .find(|suffix| finals.iter().all(|path| !with_suffix(path, suffix).exists()));
+            match suffix {
+                Some(suffix) => {
+                    let template = args::output_template(options).replace(".%(ext)s", &format!("{}.%(ext)s", suffix));
+                    Resolution {
+                        args: vec!["-o".to_string(), template],
+                        proceed: true,
+                        report: report("renamed", existing, Some(suffix)),
+                    }
+                }
+                None => Resolution { args: Vec::new(), proceed: false, report: report("skipped", existing, None) },
+            }
+        }
+        "ask" => Resolution { args: Vec::new(), proceed: false, report: report("ask", existing, None) },
+        _ => {
+            // プレイリストは存在しない項目だけをダウンロードする
+            let remaining: Vec<String> = items
+                .iter()
+                .zip(&finals)
+                .filter(|(_, path)| !path.exists())
+                .filter_map(|(item, _)| item.playlist_index.map(|index| index.to_string()))
+                .collect();
+            if remaining.is_empty() {
+                Resolution { args: Vec::new(), proceed: false, report: report("skipped", existing, None) }
+            } else {
+                Resolution {
+                    args: vec!["--no-overwrites".to_string(), "--playlist-items".to_string(), remaining.join(",")],
+                    proceed: true,
+                    report: report("partially_skipped", existing, None),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_continue_partial_downloads() {
+        let args = ["--force-overwrites", "-o", "out.%(ext)s"].map(String::from);
+        assert_eq!(retry_args(&args), ["-o", "out.%(ext)s"]);
+    }
+}
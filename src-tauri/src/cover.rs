@@ -0,0 +1,280 @@
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tokio::process::Command as TokioCommand;
+
+use crate::args::VIDEO_FORMATS;
+use crate::i18n::Message;
+use crate::tags;
+use crate::tools;
+use crate::DownloadOptions;
+
+// yt-dlpが書き出すサムネイルの拡張子
+const THUMBNAIL_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp"];
+
+// カバー画像の処理設定
+pub struct CoverSettings {
+    // "none", "square"（中央で正方形に切り抜き）, "letterbox"（黒帯の除去）
+    pub crop: String,
+    // 長辺の目標サイズ (px)
+    pub size: Option<u32>,
+    // "jpg", "png", "webp"
+    pub format: String,
+}
+
+impl CoverSettings {
+    pub fn from_options(options: &DownloadOptions) -> Self {
+        // 旧設定のthumbnail_cropは正方形クロップとして扱う
+        let crop = match options.cover_crop.as_str() {
+            "" if options.thumbnail_crop => "square",
+            "" => "none",
+            crop => crop,
+        };
+        let format = match options.cover_format.as_str() {
+            "png" | "webp" => options.cover_format.clone(),
+            _ => "jpg".to_string(),
+        };
+        CoverSettings {
+            crop: crop.to_string(),
+            size: options.cover_size.filter(|size| *size > 0),
+            format,
+        }
+    }
+}
+
+// ダウンロード後にカバー画像を処理する必要があるか
+// 動画への埋め込みはyt-dlpに任せ、音声形式への埋め込みとcover.*の保存をこちらで行う
+pub fn uses_pipeline(options: &DownloadOptions) -> bool {
+    let is_video = VIDEO_FORMATS.contains(&options.format.as_str());
+    (options.thumbnail_embed && !is_video) || saves_cover_file(options)
+}
+
+// cover.*はプレイリスト（アルバム）のフォルダにだけ保存する
+// 単体のダウンロードは同じフォルダに保存されるため、保存するたびに前のcover.*を上書きしてしまう
+pub fn saves_cover_file(options: &DownloadOptions) -> bool {
+    options.save_cover_file && options.playlist_mode
+}
+
+// クロップと縮小のフィルタ（letterboxは検出済みの範囲を渡す）
+fn filter_chain(settings: &CoverSettings, detected_crop: Option<&str>) -> Option<String> {
+    let mut filters = Vec::new();
+    match settings.crop.as_str() {
+        "square" => filters.push("crop='if(gt(ih,iw),iw,ih)':'if(gt(iw,ih),ih,iw)'".to_string()),
+        "letterbox" => {
+            if let Some(crop) = detected_crop {
+                filters.push(format!("crop={}", crop));
+            }
+        }
+        _ => {}
+    }
+    if let Some(size) = settings.size {
+        filters.push(format!("scale={}:{}:force_original_aspect_ratio=decrease", size, size));
+    }
+    if filters.is_empty() {
+        None
+    } else {
+        Some(filters.join(","))
+    }
+}
+
+// 動画へ埋め込む場合はyt-dlpのThumbnailsConvertorにフィルタを渡す（黒帯の除去は非対応）
+pub fn embed_postprocessor_args(settings: &CoverSettings) -> Option<String> {
+    filter_chain(settings, None).map(|chain| format!("ThumbnailsConvertor:-qmin 1 -q:v 1 -vf \"{}\"", chain))
+}
+
+fn ffmpeg_command(program: &str) -> TokioCommand {
+    let mut cmd = tools::command(program);
+    cmd.stdin(std::process::Stdio::null());
+    cmd
+}
+
+// 画像の解像度を取得
+async fn probe_dimensions(image: &Path) -> Option<(u64, u64)> {
+    let output = ffmpeg_command(&tools::ffprobe())
+        .args(["-v", "error", "-select_streams", "v:0", "-show_entries", "stream=width,height", "-of", "csv=p=0:s=x"])
+        .arg(image)
+        .output()
+        .await
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (width, height) = text.trim().split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+// サムネイル一覧から最も解像度の高いもののURLを選ぶ
+fn best_thumbnail(thumbnails: &Value) -> Option<(String, u64)> {
+    thumbnails
+        .as_array()?
+        .iter()
+        .filter_map(|thumb| {
+            let url = thumb.get("url")?.as_str()?;
+            let width = thumb.get("width")?.as_u64()?;
+            let height = thumb.get("height")?.as_u64()?;
+            Some((url.to_string(), width * height))
+        })
+        .max_by_key(|(_, pixels)| *pixels)
+}
+
+// cropdetectで黒帯を除いた範囲 (w:h:x:y) を検出する
+async fn detect_letterbox(source: &str) -> Option<String> {
+    // 古いffmpegでも先頭フレームを解析対象にできるよう、静止画を数フレーム分ループさせる
+    let output = ffmpeg_command(&tools::ffmpeg())
+        .args(["-hide_banner", "-loop", "1", "-i", source, "-vf", "cropdetect=limit=24:round=2:reset=0", "-frames:v", "3", "-f", "null", "-"])
+        .output()
+        .await
+        .ok()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stderr
+        .rsplit("crop=")
+        .next()
+        .filter(|_| stderr.contains("crop="))
+        .map(|rest| rest.split_whitespace().next().unwrap_or_default().to_string())
+        .filter(|crop| crop.split(':').count() == 4)
+}
+
+async fn convert(source: &str, output: &Path, format: &str, chain: Option<&str>) -> Result<(), Message> {
+    let mut cmd = ffmpeg_command(&tools::ffmpeg());
+    cmd.args(["-hide_banner", "-y", "-i", source, "-frames:v", "1"]);
+    if let Some(chain) = chain {
+        cmd.args(["-vf", chain]);
+    }
+    match format {
+        "png" => cmd.args(["-c:v", "png"]),
+        "webp" => cmd.args(["-c:v", "libwebp", "-quality", "90"]),
+        _ => cmd.args(["-c:v", "mjpeg", "-q:v", "2", "-pix_fmt", "yuvj420p"]),
+    };
+    let result = cmd.arg(output).output().await.map_err(|e| Message::new("command.run_failed").arg("program", "ffmpeg").arg("error", e))?;
+    if result.status.success() {
+        Ok(())
+    } else {
+        Err(Message::new("cover.convert_failed").arg("error", String::from_utf8_lossy(&result.stderr).lines().last().unwrap_or_default()))
+    }
+}
+
+// カバー画像だけを差し替えるタグの変更
+fn embed_edit(image: &Path) -> tags::TagEdit {
+    tags::TagEdit {
+        cover_path: Some(image.display().to_string()),
+        ..Default::default()
+    }
+}
+
+// ダウンロード済みファイルのカバー画像を作成し、埋め込み・保存する
+// 戻り値は保存したcover.*のパス
+pub async fn process(
+    media: &Path,
+    thumbnails: Option<&Value>,
+    settings: &CoverSettings,
+    embed: bool,
+    save_cover_file: bool,
+) -> Result<Option<PathBuf>, Message> {
+    let stem = media
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .ok_or_else(|| Message::new("common.file_name_unavailable"))?;
+    let dir = media.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+    // yt-dlpが書き出したサムネイル
+    let written = THUMBNAIL_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", stem, ext)))
+        .find(|path| path.exists());
+
+    // より高解像度のサムネイルがあればURLから直接読み込む
+    let mut source = written.as_ref().map(|path| path.display().to_string());
+    if let Some((url, pixels)) = thumbnails.and_then(best_thumbnail) {
+        let written_pixels = match &written {
+            Some(path) => probe_dimensions(path).await.map(|(w, h)| w * h).unwrap_or(0),
+            None => 0,
+        };
+        if pixels > written_pixels {
+            source = Some(url);
+        }
+    }
+    let source = source.ok_or_else(|| Message::new("cover.thumbnail_not_found"))?;
+
+    let detected = if settings.crop == "letterbox" {
+        detect_letterbox(&source).await
+    } else {
+        None
+    };
+    let chain = filter_chain(settings, detected.as_deref());
+
+    let processed = dir.join(format!("{}.necd-cover.{}", stem, settings.format));
+    let mut result = convert(&source, &processed, &settings.format, chain.as_deref()).await;
+    if result.is_err() && written.as_ref().is_some_and(|path| path.display().to_string() != source) {
+        // URLから読み込めなかった場合は書き出し済みのサムネイルで再試行
+        let fallback = written.as_ref().map(|path| path.display().to_string()).unwrap_or_default();
+        result = convert(&fallback, &processed, &settings.format, chain.as_deref()).await;
+    }
+    result?;
+
+    let mut temp_files = vec![processed.clone()];
+    let outcome = async {
+        if embed {
+            // WebPはmp3/m4aに埋め込めないためJPEGに変換して埋め込む
+            let embed_image = if settings.format == "webp" {
+                let jpg = dir.join(format!("{}.necd-cover.jpg", stem));
+                convert(&processed.display().to_string(), &jpg, "jpg", None).await?;
+                temp_files.push(jpg.clone());
+                jpg
+            } else {
+                processed.clone()
+            };
+            tags::write_tags(media, &embed_edit(&embed_image)).await?;
+        }
+
+        if save_cover_file {
+            let cover_file = dir.join(format!("cover.{}", settings.format));
+            std::fs::copy(&processed, &cover_file).map_err(|e| Message::new("cover.save_failed").arg("error", e))?;
+            return Ok(Some(cover_file));
+        }
+        Ok(None)
+    }
+    .await;
+
+    for file in temp_files {
+        let _ = std::fs::remove_file(file);
+    }
+    if let Some(written) = written {
+        let _ = std::fs::remove_file(written);
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn embed_args(file: &str) -> Vec<String> {
+        let edit = embed_edit(Path::new("song.necd-cover.jpg"));
+        tags::write_args(Path::new(file), Path::new("meta.txt"), Path::new("temp"), &edit, false)
+            .iter()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn embeds_cover_as_attached_picture() {
+        for file in ["song.mp3", "song.m4a", "song.flac"] {
+            let args = embed_args(file);
+            let inputs: Vec<&str> = args
+                .iter()
+                .enumerate()
+                .filter(|(_, arg)| *arg == "-i")
+                .map(|(index, _)| args[index + 1].as_str())
+                .collect();
+            assert_eq!(inputs, [file, "meta.txt", "song.necd-cover.jpg"]);
+            let first_map = args.iter().position(|arg| arg == "-map").unwrap();
+            assert!(args.iter().rposition(|arg| arg == "-i").unwrap() < first_map);
+            assert_eq!(&args[first_map..first_map + 4], ["-map", "0:a", "-map", "2:v"]);
+            assert!(args.windows(2).any(|pair| pair == ["-disposition:v", "attached_pic"]));
+        }
+    }
+
+    #[test]
+    fn embeds_opus_cover_through_metadata() {
+        let args = embed_args("song.opus");
+        assert!(!args.iter().any(|arg| arg == "song.necd-cover.jpg"));
+        assert!(args.windows(2).any(|pair| pair == ["-map_metadata:s:a", "1:g"]));
+    }
+}
@@ -0,0 +1,184 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::events::EventSink;
+use crate::i18n::Message;
+use crate::queue;
+use crate::settings;
+
+pub const SCHEME: &str = "necd";
+// これより長いリンクは受け付けない
+const MAX_LINK_LENGTH: usize = 8192;
+// App.vueの形式の選択肢と同じ
+const FORMATS: &[&str] = &["mp4", "mp3", "mkv", "webm", "mov", "opus", "flac"];
+
+// necd://download?url=...&preset=music の内容
+// リンクはWebページからも開けるため、保存先やCookie、ツールのパスなどは指定できないようにしている
+#[derive(Debug, Serialize, Clone)]
+pub struct DeepLink {
+    pub url: String,
+    pub preset: Option<String>,
+    // DownloadOptionsの項目名（snake_case）と値
+    pub overrides: Map<String, Value>,
+}
+
+// "deep-link"イベントでフロントエンドに送る結果
+#[derive(Debug, Serialize, Clone)]
+pub struct DeepLinkResult {
+    pub link: String,
+    pub job: Option<queue::JobInfo>,
+    pub error: Option<Message>,
+}
+
+fn invalid(key: &str, link: &str) -> Message {
+    Message::new(key).arg("link", link)
+}
+
+fn is_deep_link(text: &str) -> bool {
+    text.trim().to_lowercase().starts_with(&format!("{}:", SCHEME))
+}
+
+fn is_media_url(url: &url::Url) -> bool {
+    matches!(url.scheme(), "http" | "https") && url.host_str().is_some_and(|host| !host.is_empty())
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+// リンクを解釈し、指定できる項目のみを取り出す
+pub fn parse(link: &str) -> Result<DeepLink, Message> {
+    let link = link.trim();
+    if link.len() > MAX_LINK_LENGTH {
+        return Err(invalid("deeplink.invalid", &link.chars().take(64).collect::<String>()));
+    }
+    let parsed = url::Url::parse(link).map_err(|_| invalid("deeplink.invalid", link))?;
+    if parsed.scheme() != SCHEME {
+        return Err(invalid("deeplink.invalid", link));
+    }
+    // necd://download?... と necd:download?... のどちらも受け付ける
+    let action = parsed
+        .host_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| parsed.path().trim_matches('/').to_string());
+    if action != "download" {
+        return Err(Message::new("deeplink.unsupported_action").arg("action", action));
+    }
+
+    let mut url = None;
+    let mut preset = None;
+    let mut overrides = Map::new();
+    for (key, value) in parsed.query_pairs() {
+        let value = value.trim();
+        match key.as_ref() {
+            "url" => {
+                let media = url::Url::parse(value)
+                    .ok()
+                    .filter(is_media_url)
+                    .ok_or_else(|| Message::new("deeplink.invalid_url").arg("url", value))?;
+                url = Some(media.to_string());
+            }
+            "preset" => preset = Some(value.to_string()),
+            "format" => {
+                if !FORMATS.contains(&value) {
+                    return Err(Message::new("deeplink.invalid_value").arg("name", "format").arg("value", value));
+                }
+                overrides.insert("format".to_string(), Value::String(value.to_string()));
+            }
+            "quality" => {
+                if value.is_empty() || value.len() > 16 || !value.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return Err(Message::new("deeplink.invalid_value").arg("name", "quality").arg("value", value));
+                }
+                overrides.insert("quality".to_string(), Value::String(value.to_string()));
+            }
+            "playlist" => {
+                let playlist = parse_bool(value)
+                    .ok_or_else(|| Message::new("deeplink.invalid_value").arg("name", "playlist").arg("value", value))?;
+                overrides.insert("playlist_mode".to_string(), Value::Bool(playlist));
+            }
+            // 知らない項目は無視する（新しいバージョン向けのリンクでも開けるように）
+            _ => {}
+        }
+    }
+    let url = url.ok_or_else(|| invalid("deeplink.missing_url", link))?;
+
+    Ok(DeepLink { url, preset, overrides })
+}
+
+async fn enqueue(link: &str) -> Result<queue::JobInfo, Message> {
+    let (url, preset, overrides) = if is_deep_link(link) {
+        let parsed = parse(link)?;
+        (parsed.url, parsed.preset, parsed.overrides)
+    } else {
+        // 引数にURLだけが渡された場合は保存された設定でダウンロードする
+        let media = url::Url::parse(link.trim())
+            .ok()
+            .filter(is_media_url)
+            .ok_or_else(|| Message::new("deeplink.invalid_url").arg("url", link))?;
+        (media.to_string(), None, Map::new())
+    };
+    // プリセットの値より個別に指定した値を優先する
+    let options = settings::options_for(&url, preset.as_deref(), &overrides).await?;
+    Ok(queue::enqueue(options, "deeplink"))
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn handle(app: &AppHandle, links: Vec<String>) {
+    if links.is_empty() {
+        return;
+    }
+    show_main_window(app);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        for link in links {
+            let result = match enqueue(&link).await {
+                Ok(job) => DeepLinkResult { link, job: Some(job), error: None },
+                Err(e) => DeepLinkResult { link, job: None, error: Some(e) },
+            };
+            app.send("deep-link", result);
+        }
+    });
+}
+
+// 起動時の引数、または2つ目の起動から転送された引数のうち、リンクとURLを処理する
+pub fn handle_args(app: &AppHandle, args: &[String]) {
+    let links = args
+        .iter()
+        .filter(|arg| {
+            let lower = arg.to_lowercase();
+            is_deep_link(arg) || lower.starts_with("http://") || lower.starts_with("https://")
+        })
+        .cloned()
+        .collect();
+    handle(app, links);
+}
+
+// OSから開かれたリンクを受け取る（macOSは起動時のリンクもここに届く）
+pub fn init(app: &AppHandle) {
+    let handle_app = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        handle(&handle_app, event.urls().into_iter().map(|url| url.to_string()).collect());
+    });
+
+    // インストーラーを使わずに起動した場合（開発時やAppImage）もスキームを登録する
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        eprintln!("{}", Message::new("deeplink.register_failed").arg("error", e).text());
+    }
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    handle_args(app, &args);
+}
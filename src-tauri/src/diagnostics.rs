@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::i18n::Message;
+use crate::joblog;
+use crate::tools;
+
+// yt-dlpの更新を勧めるまでの日数
+const YT_DLP_MAX_AGE_DAYS: i64 = 60;
+// 空き容量が少ないと警告する閾値 (GB)
+const LOW_FREE_SPACE_GB: f64 = 5.0;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiagnosticItem {
+    pub id: String,
+    pub label: String,
+    // "pass", "warn", "fail", "skipped"（前提の項目が失敗したため確認していない）
+    pub status: String,
+    pub detail: String,
+    // 問題がある場合の対処方法
+    pub suggestion: Option<String>,
+    // 前提となる項目のid
+    pub depends_on: Option<String>,
+}
+
+fn item(id: &str, label: &str, status: &str, detail: String, suggestion: Option<String>) -> DiagnosticItem {
+    DiagnosticItem {
+        id: id.to_string(),
+        label: label.to_string(),
+        status: status.to_string(),
+        detail,
+        suggestion,
+        depends_on: None,
+    }
+}
+
+// コマンドを実行し、成功した場合は標準出力を返す
+async fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let mut cmd = tools::command(program);
+    cmd.args(args).stdin(std::process::Stdio::null());
+    let output = tokio::time::timeout(Duration::from_secs(15), cmd.output())
+        .await
+        .map_err(|_| Message::new("diagnostics.no_response").text())?
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(Message::new("diagnostics.exit_code")
+            .arg("code", output.status.code().unwrap_or(-1))
+            .arg("error", String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or_default())
+            .text())
+    }
+}
+
+// YYYY.MM.DD形式のバージョンからリリースからの経過日数を求める
+fn version_age_days(version: &str) -> Option<i64> {
+    let mut parts = version.trim().split('.').map(|part| part.parse::<i64>());
+    let (year, month, day) = (parts.next()?.ok()?, parts.next()?.ok()?, parts.next()?.ok()?);
+    let today = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs() as i64
+        / 86400;
+    Some(today - joblog::days_from_civil(year, month, day))
+}
+
+async fn check_yt_dlp() -> DiagnosticItem {
+    let path = tools::yt_dlp();
+    match run(&path, &["--version"]).await {
+        Ok(output) => {
+            let version = output.trim().to_string();
+            match version_age_days(&version) {
+                Some(age) if age > YT_DLP_MAX_AGE_DAYS => item(
+                    "yt_dlp",
+                    "yt-dlp",
+                    "warn",
+                    Message::new("diagnostics.yt_dlp_age").arg("version", &version).arg("days", age).arg("path", &path).text(),
+                    Some(Message::new("diagnostics.yt_dlp_outdated").text()),
+                ),
+                Some(age) => item(
+                    "yt_dlp",
+                    "yt-dlp",
+                    "pass",
+                    Message::new("diagnostics.yt_dlp_age").arg("version", &version).arg("days", age).arg("path", &path).text(),
+                    None,
+                ),
+                None => item("yt_dlp", "yt-dlp", "pass", format!("{}: {}", version, path), None),
+            }
+        }
+        Err(e) => item(
+            "yt_dlp",
+            "yt-dlp",
+            "fail",
+            Message::new("diagnostics.run_failed").arg("path", &path).arg("error", e).text(),
+            Some(Message::new("diagnostics.yt_dlp_missing").text()),
+        ),
+    }
+}
+
+async fn check_ffmpeg_tool(id: &str, path: &str) -> DiagnosticItem {
+    match run(path, &["-hide_banner", "-version"]).await {
+        Ok(output) => {
+            let version = output.lines().next().unwrap_or_default().to_string();
+            item(id, id, "pass", format!("{}: {}", version, path), None)
+        }
+        Err(e) => item(
+            id,
+            id,
+            "fail",
+            Message::new("diagnostics.run_failed").arg("path", path).arg("error", e).text(),
+            Some(Message::new("diagnostics.ffmpeg_missing").text()),
+        ),
+    }
+}
+
+// 音声の変換に必要なエンコーダー（ffmpegを実行できない場合は確認しない）
+async fn check_codecs(ffmpeg: &str, ffmpeg_available: bool) -> Vec<DiagnosticItem> {
+    let encoders = if ffmpeg_available {
+        run(ffmpeg, &["-hide_banner", "-encoders"]).await.unwrap_or_default()
+    } else {
+        String::new()
+    };
+    [("libmp3lame", "MP3"), ("libopus", "Opus")]
+        .iter()
+        .map(|(encoder, format)| {
+            let id = format!("codec_{}", encoder);
+            let label = Message::new("diagnostics.codec").arg("format", format).arg("encoder", encoder).text();
+            let item = if !ffmpeg_available {
+                item(&id, &label, "skipped", Message::new("diagnostics.codec_skipped").text(), None)
+            } else if encoders.lines().any(|line| line.split_whitespace().nth(1) == Some(*encoder)) {
+                item(&id, &label, "pass", Message::new("diagnostics.codec_available").text(), None)
+            } else {
+                item(
+                    &id,
+                    &label,
+                    "fail",
+                    Message::new("diagnostics.codec_missing").arg("encoder", encoder).text(),
+                    Some(Message::new("diagnostics.codec_suggestion").text()),
+                )
+            };
+            DiagnosticItem {
+                depends_on: Some("ffmpeg".to_string()),
+                ..item
+            }
+        })
+        .collect()
+}
+
+fn check_output_directory(directory: &str) -> Vec<DiagnosticItem> {
+    let label = Message::new("diagnostics.output_directory").text();
+    if directory.trim().is_empty() {
+        return vec![item(
+            "output_directory",
+            &label,
+            "warn",
+            Message::new("diagnostics.directory_unset").text(),
+            Some(Message::new("diagnostics.directory_unset_suggestion").text()),
+        )];
+    }
+    let dir = Path::new(directory);
+    if !dir.is_dir() {
+        return vec![item(
+            "output_directory",
+            &label,
+            "fail",
+            Message::new("diagnostics.directory_missing").arg("path", directory).text(),
+            Some(Message::new("diagnostics.directory_missing_suggestion").text()),
+        )];
+    }
+
+    let mut items = Vec::new();
+    let probe = dir.join(format!(".necd-write-test-{}", std::process::id()));
+    match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            items.push(item(
+                "output_directory",
+                &label,
+                "pass",
+                Message::new("diagnostics.directory_writable").arg("path", directory).text(),
+                None,
+            ));
+        }
+        Err(e) => items.push(item(
+            "output_directory",
+            &label,
+            "fail",
+            Message::new("diagnostics.directory_not_writable").arg("path", directory).arg("error", e).text(),
+            Some(Message::new("diagnostics.directory_not_writable_suggestion").text()),
+        )),
+    }
+
+    let label = Message::new("diagnostics.free_space").text();
+    match fs2::available_space(dir) {
+        Ok(bytes) => {
+            let gb = bytes as f64 / 1024.0 / 1024.0 / 1024.0;
+            let detail = Message::new("diagnostics.free_space_left").arg("size", format!("{:.1}", gb)).text();
+            if gb < LOW_FREE_SPACE_GB {
+                items.push(item(
+                    "free_space",
+                    &label,
+                    "warn",
+                    detail,
+                    Some(Message::new("diagnostics.free_space_low").text()),
+                ));
+            } else {
+                items.push(item("free_space", &label, "pass", detail, None));
+            }
+        }
+        Err(e) => items.push(item(
+            "free_space",
+            &label,
+            "warn",
+            Message::new("diagnostics.free_space_unavailable").arg("error", e).text(),
+            None,
+        )),
+    }
+    items
+}
+
+// Netscape形式のCookieファイルか、有効期限の切れていないCookieがあるかを確認する
+fn check_cookie_file(path: Option<&str>) -> Option<DiagnosticItem> {
+    let path = path.map(str::trim).filter(|path| !path.is_empty())?;
+    let label = Message::new("diagnostics.cookie_file").text();
+    let suggestion = Some(Message::new("diagnostics.cookie_suggestion").text());
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            let detail = Message::new("diagnostics.cookie_unreadable").arg("path", path).arg("error", e).text();
+            return Some(item("cookie_file", &label, "fail", detail, suggestion));
+        }
+    };
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let mut total = 0;
+    let mut valid = 0;
+    for line in content.lines() {
+        // #HttpOnly_ で始まる行はCookieとして扱う
+        let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            continue;
+        }
+        total += 1;
+        // 有効期限0はセッションCookie
+        let expires = fields[4].parse::<i64>().unwrap_or(0);
+        if expires == 0 || expires > now {
+            valid += 1;
+        }
+    }
+
+    Some(if total == 0 {
+        item("cookie_file", &label, "fail", Message::new("diagnostics.cookie_none").arg("path", path).text(), suggestion)
+    } else if valid == 0 {
+        item("cookie_file", &label, "warn", Message::new("diagnostics.cookie_expired").arg("total", total).text(), suggestion)
+    } else {
+        let detail = Message::new("diagnostics.cookie_valid").arg("valid", valid).arg("total", total).text();
+        item("cookie_file", &label, "pass", detail, None)
+    })
+}
+
+// プロキシのホストに接続できるかを確認する
+async fn check_proxy(proxy: Option<String>) -> Option<DiagnosticItem> {
+    let proxy = proxy
+        .filter(|proxy| !proxy.trim().is_empty())
+        .or_else(|| ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]
+            .iter()
+            .find_map(|key| std::env::var(key).ok().filter(|value| !value.is_empty())))?;
+
+    let without_scheme = proxy.split_once("://").map(|(_, rest)| rest).unwrap_or(&proxy);
+    let host_port = without_scheme
+        .rsplit('@')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/')
+        .to_string();
+    let address = if host_port.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok()) {
+        host_port
+    } else {
+        format!("{}:{}", host_port, if proxy.starts_with("https") { 443 } else { 8080 })
+    };
+
+    let label = Message::new("diagnostics.proxy").text();
+    let suggestion = Some(Message::new("diagnostics.proxy_suggestion").text());
+    Some(match tokio::time::timeout(Duration::from_secs(5), tokio::net::TcpStream::connect(&address)).await {
+        Ok(Ok(_)) => item("proxy", &label, "pass", Message::new("diagnostics.proxy_connected").arg("address", &address).text(), None),
+        Ok(Err(e)) => {
+            let detail = Message::new("diagnostics.proxy_failed").arg("address", &address).arg("error", e).text();
+            item("proxy", &label, "fail", detail, suggestion)
+        }
+        Err(_) => item("proxy", &label, "fail", Message::new("diagnostics.proxy_timeout").arg("address", &address).text(), suggestion),
+    })
+}
+
+pub async fn run_all(output_directory: &str, cookie_file_path: Option<&str>, proxy: Option<String>) -> Vec<DiagnosticItem> {
+    let ffmpeg = tools::ffmpeg();
+    let ffprobe = tools::ffprobe();
+
+    let ffmpeg_item = check_ffmpeg_tool("ffmpeg", &ffmpeg).await;
+    let ffmpeg_available = ffmpeg_item.status == "pass";
+    let mut items = vec![
+        check_yt_dlp().await,
+        ffmpeg_item,
+        check_ffmpeg_tool("ffprobe", &ffprobe).await,
+    ];
+    items.extend(check_codecs(&ffmpeg, ffmpeg_available).await);
+    items.extend(check_output_directory(output_directory));
+    items.extend(check_cookie_file(cookie_file_path));
+    items.extend(check_proxy(proxy).await);
+    items
+}
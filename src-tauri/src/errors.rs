@@ -0,0 +1,297 @@
+use serde::{Deserialize, Serialize};
+
+use crate::i18n;
+
+// yt-dlpのエラーの種類
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    PrivateVideo,
+    MembersOnly,
+    // 年齢制限・ログインやボット確認が必要
+    LoginRequired,
+    GeoBlocked,
+    Removed,
+    Http403,
+    Http429,
+    FfmpegMissing,
+    UnsupportedUrl,
+    DiskFull,
+    ExtractorOutdated,
+    // 接続の切断やタイムアウトなど
+    Network,
+    Unknown,
+}
+
+// 分類したエラー（元のメッセージ付き）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DownloadError {
+    pub kind: ErrorKind,
+    // 再試行で解決する可能性がある
+    pub retryable: bool,
+    // 利用者向けの説明
+    pub message: String,
+    // 説明のメッセージキー
+    pub i18n: i18n::Message,
+    // yt-dlpが出力したERROR:行
+    pub raw: String,
+}
+
+// 判定に使う文字列（ERROR:の後のメッセージを小文字で比較する）。上から順に判定する
+// 短い語は他のエラーの説明にも含まれるため、yt-dlpが実際に出力する言い回しで指定する
+const PATTERNS: &[(ErrorKind, &[&str])] = &[
+    (ErrorKind::DiskFull, &["no space left on device", "errno 28", "not enough space on the disk", "disk full"]),
+    (ErrorKind::FfmpegMissing, &["ffmpeg not found", "ffprobe not found", "ffmpeg is not installed", "ffprobe and ffmpeg not found"]),
+    (ErrorKind::UnsupportedUrl, &["unsupported url"]),
+    (ErrorKind::PrivateVideo, &["private video", "video is private"]),
+    (ErrorKind::MembersOnly, &["members-only", "members only", "join this channel", "available to this channel's members"]),
+    (ErrorKind::LoginRequired, &[
+        "sign in to confirm your age",
+        "age-restricted",
+        "inappropriate for some users",
+        "sign in to confirm you",
+        "login required",
+        "requires authentication",
+        "use --cookies",
+    ]),
+    (ErrorKind::GeoBlocked, &["available in your country", "geo restrict", "geo-restrict", "blocked it in your country", "not available from your location"]),
+    (ErrorKind::Removed, &[
+        "has been removed",
+        "video unavailable",
+        "no longer available",
+        "account associated with this video has been terminated",
+        "this channel does not exist",
+        "playlist does not exist",
+        "http error 404",
+    ]),
+    (ErrorKind::Http429, &["http error 429", "too many requests"]),
+    (ErrorKind::Http403, &["http error 403", "403: forbidden"]),
+    (ErrorKind::ExtractorOutdated, &[
+        "unable to extract",
+        "please report this issue",
+        "nsig extraction failed",
+        "signature extraction failed",
+        "failed to parse json",
+    ]),
+    (ErrorKind::Network, &[
+        "timed out",
+        "connection reset",
+        "connection refused",
+        "connection aborted",
+        "remote end closed connection",
+        "temporary failure in name resolution",
+        "getaddrinfo failed",
+        "unable to download webpage",
+        "fragment retries",
+        "not found, unable to continue",
+        "incompleteread",
+    ]),
+];
+
+impl ErrorKind {
+    pub fn retryable(self) -> bool {
+        matches!(self, ErrorKind::Http403 | ErrorKind::Http429 | ErrorKind::Network)
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            ErrorKind::PrivateVideo => "error.private_video",
+            ErrorKind::MembersOnly => "error.members_only",
+            ErrorKind::LoginRequired => "error.login_required",
+            ErrorKind::GeoBlocked => "error.geo_blocked",
+            ErrorKind::Removed => "error.removed",
+            ErrorKind::Http403 => "error.http403",
+            ErrorKind::Http429 => "error.http429",
+            ErrorKind::FfmpegMissing => "error.ffmpeg_missing",
+            ErrorKind::UnsupportedUrl => "error.unsupported_url",
+            ErrorKind::DiskFull => "error.disk_full",
+            ErrorKind::ExtractorOutdated => "error.extractor_outdated",
+            ErrorKind::Network => "error.network",
+            ErrorKind::Unknown => "error.unknown",
+        }
+    }
+
+    pub fn message(self) -> String {
+        i18n::Message::new(self.key()).text()
+    }
+}
+
+// ERROR:で始まる行を分類する（それ以外の行はNone）
+// 進捗やWARNING:行、動画のタイトルなどに含まれる語で誤判定しないよう、行頭のERROR:のみを対象にする
+pub fn classify(line: &str) -> Option<DownloadError> {
+    let raw = line.trim();
+    let lower = raw.strip_prefix("ERROR:")?.to_lowercase();
+    let raw = raw.to_string();
+    let kind = PATTERNS
+        .iter()
+        .find(|(_, needles)| needles.iter().any(|needle| lower.contains(needle)))
+        .map(|(kind, _)| *kind)
+        .unwrap_or(ErrorKind::Unknown);
+    Some(DownloadError {
+        kind,
+        retryable: kind.retryable(),
+        message: kind.message(),
+        i18n: i18n::Message::new(kind.key()),
+        raw,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(line: &str) -> Option<ErrorKind> {
+        classify(line).map(|error| error.kind)
+    }
+
+    #[test]
+    fn classifies_disk_full() {
+        assert_eq!(kind("ERROR: unable to write data: [Errno 28] No space left on device"), Some(ErrorKind::DiskFull));
+        assert_eq!(
+            kind("ERROR: unable to write data: [WinError 112] There is not enough space on the disk"),
+            Some(ErrorKind::DiskFull)
+        );
+    }
+
+    #[test]
+    fn classifies_ffmpeg_missing() {
+        assert_eq!(
+            kind("ERROR: Postprocessing: ffprobe and ffmpeg not found. Please install or provide the path using --ffmpeg-location"),
+            Some(ErrorKind::FfmpegMissing)
+        );
+        assert_eq!(
+            kind("ERROR: You have requested merging of multiple formats but ffmpeg is not installed. Aborting due to --abort-on-error"),
+            Some(ErrorKind::FfmpegMissing)
+        );
+    }
+
+    #[test]
+    fn classifies_unsupported_url() {
+        assert_eq!(kind("ERROR: Unsupported URL: https://example.com/page"), Some(ErrorKind::UnsupportedUrl));
+    }
+
+    #[test]
+    fn classifies_private_video() {
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: Private video. Sign in if you've been granted access to this video"),
+            Some(ErrorKind::PrivateVideo)
+        );
+    }
+
+    #[test]
+    fn classifies_members_only() {
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: Join this channel to get access to members-only content like this video, and other exclusive perks."),
+            Some(ErrorKind::MembersOnly)
+        );
+    }
+
+    #[test]
+    fn classifies_login_required() {
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: Sign in to confirm your age. This video may be inappropriate for some users. Use --cookies-from-browser or --cookies for the authentication."),
+            Some(ErrorKind::LoginRequired)
+        );
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: Sign in to confirm you\u{2019}re not a bot. Use --cookies-from-browser or --cookies for the authentication."),
+            Some(ErrorKind::LoginRequired)
+        );
+    }
+
+    #[test]
+    fn classifies_geo_blocked() {
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: Video unavailable. The uploader has not made this video available in your country"),
+            Some(ErrorKind::GeoBlocked)
+        );
+        assert_eq!(
+            kind("ERROR: [niconico] sm9: This video is not available from your location due to geo restriction"),
+            Some(ErrorKind::GeoBlocked)
+        );
+    }
+
+    #[test]
+    fn classifies_removed() {
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: Video unavailable. This video has been removed by the uploader"),
+            Some(ErrorKind::Removed)
+        );
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: Video unavailable. This video is no longer available because the YouTube account associated with this video has been terminated."),
+            Some(ErrorKind::Removed)
+        );
+        assert_eq!(
+            kind("ERROR: [youtube:tab] UCxxxxxxxxxxxxxxxxxxxxxx: This channel does not exist."),
+            Some(ErrorKind::Removed)
+        );
+        assert_eq!(
+            kind("ERROR: [generic] Unable to download webpage: HTTP Error 404: Not Found (caused by <HTTPError 404: Not Found>)"),
+            Some(ErrorKind::Removed)
+        );
+    }
+
+    #[test]
+    fn classifies_http_errors() {
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: Unable to download API page: HTTP Error 429: Too Many Requests (caused by <HTTPError 429: Too Many Requests>)"),
+            Some(ErrorKind::Http429)
+        );
+        assert_eq!(kind("ERROR: unable to download video data: HTTP Error 403: Forbidden"), Some(ErrorKind::Http403));
+    }
+
+    #[test]
+    fn classifies_extractor_outdated() {
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: Unable to extract uploader id; please report this issue on  https://github.com/yt-dlp/yt-dlp/issues?q= , filling out the appropriate issue template. Confirm you are on the latest version using  yt-dlp -U"),
+            Some(ErrorKind::ExtractorOutdated)
+        );
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: nsig extraction failed: You may experience throttling for some formats"),
+            Some(ErrorKind::ExtractorOutdated)
+        );
+    }
+
+    #[test]
+    fn classifies_network() {
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: Unable to download webpage: The read operation timed out (caused by TransportError('The read operation timed out'))"),
+            Some(ErrorKind::Network)
+        );
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: Unable to download webpage: [Errno -3] Temporary failure in name resolution"),
+            Some(ErrorKind::Network)
+        );
+        assert_eq!(kind("ERROR: fragment 1 not found, unable to continue"), Some(ErrorKind::Network));
+        assert_eq!(kind("ERROR: giving up after 10 fragment retries"), Some(ErrorKind::Network));
+        assert_eq!(
+            kind("ERROR: ('Connection broken: IncompleteRead(0 bytes read, 1024 more expected)', IncompleteRead(0 bytes read, 1024 more expected))"),
+            Some(ErrorKind::Network)
+        );
+    }
+
+    #[test]
+    fn unmatched_errors_are_unknown() {
+        assert_eq!(
+            kind("ERROR: [youtube] dQw4w9WgXcQ: Requested format is not available. Use --list-formats for a list of available formats"),
+            Some(ErrorKind::Unknown)
+        );
+        assert_eq!(kind("ERROR: Postprocessing: Conversion failed!"), Some(ErrorKind::Unknown));
+    }
+
+    #[test]
+    fn ignores_lines_without_leading_error() {
+        assert_eq!(kind("[download] Downloading fragment 3 of 120"), None);
+        assert_eq!(
+            kind("WARNING: [youtube] dQw4w9WgXcQ: nsig extraction failed. Confirm you are on the latest version using  yt-dlp -U"),
+            None
+        );
+        assert_eq!(kind("[download] Destination: ERROR: fragment does not exist.mp4"), None);
+    }
+
+    #[test]
+    fn keeps_raw_line_and_retryable() {
+        let error = classify("  ERROR: unable to download video data: HTTP Error 403: Forbidden\r").unwrap();
+        assert_eq!(error.raw, "ERROR: unable to download video data: HTTP Error 403: Forbidden");
+        assert!(error.retryable);
+    }
+}
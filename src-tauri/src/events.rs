@@ -0,0 +1,19 @@
+use serde::Serialize;
+use tauri::Emitter;
+
+// ダウンロード処理のイベントの送り先（GUIではウィンドウ、CLIでは端末）
+pub trait EventSink: Clone + Send + Sync + 'static {
+    fn send<S: Serialize + Clone>(&self, event: &str, payload: S);
+}
+
+impl<R: tauri::Runtime> EventSink for tauri::Window<R> {
+    fn send<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        let _ = self.emit(event, payload);
+    }
+}
+
+impl<R: tauri::Runtime> EventSink for tauri::AppHandle<R> {
+    fn send<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        let _ = self.emit(event, payload);
+    }
+}
@@ -0,0 +1,430 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    Ja,
+    En,
+}
+
+// キーと引数で表したメッセージ（UIはキーから任意の言語で表示できる）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Message {
+    pub key: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Catalog {
+    pub locale: Locale,
+    // キーとテンプレート（{name}が引数に置き換わる）
+    pub messages: BTreeMap<String, String>,
+}
+
+// キー、日本語、英語
+const CATALOG: &[(&str, &str, &str)] = &[
+    // 共通
+    ("common.detail", "{detail}", "{detail}"),
+    ("common.warning", "⚠️ {detail}", "⚠️ {detail}"),
+    ("common.error", "❌ {detail}", "❌ {detail}"),
+    ("command.spawn_failed", "コマンド実行エラー: {error}", "Failed to run command: {error}"),
+    ("options.invalid", "オプションが正しくありません: {error}", "Invalid options: {error}"),
+    ("command.wait_failed", "プロセス待機エラー: {error}", "Failed to wait for process: {error}"),
+    ("command.run_failed", "{program}実行エラー: {error}", "Failed to run {program}: {error}"),
+    ("command.failed", "{program}エラー: {error}", "{program} error: {error}"),
+    ("command.pipe_failed", "{stream}取得エラー", "Could not capture {stream}"),
+    ("command.read_failed", "{stream}読み取りエラー: {error}", "Failed to read {stream}: {error}"),
+    ("common.config_dir_unavailable", "設定ディレクトリを取得できませんでした", "Could not find the settings directory"),
+    ("common.config_dir_create_failed", "設定ディレクトリを作成できませんでした: {error}", "Could not create the settings directory: {error}"),
+    ("common.data_dir_unavailable", "データディレクトリを取得できませんでした", "Could not find the data directory"),
+    ("common.dir_create_failed", "ディレクトリを作成できませんでした: {error}", "Could not create the directory: {error}"),
+    ("common.file_name_unavailable", "ファイル名を取得できませんでした", "Could not get the file name"),
+    ("common.json_parse_failed", "JSONパースエラー: {error}", "Failed to parse JSON: {error}"),
+    ("common.read_failed", "ファイルの読み込みに失敗しました: {error}", "Failed to read the file: {error}"),
+    ("common.write_failed", "ファイルの書き込みに失敗しました: {error}", "Failed to write the file: {error}"),
+    ("common.replace_failed", "ファイルを置き換えられませんでした: {error}", "Could not replace the file: {error}"),
+    ("common.read_dir_failed", "フォルダを読み込めませんでした: {error}", "Could not read the folder: {error}"),
+    ("common.output_save_failed", "出力ファイルを保存できませんでした: {error}", "Could not save the output file: {error}"),
+    ("common.ffmpeg_failed", "ffmpegの処理に失敗しました: {error}", "ffmpeg failed: {error}"),
+    ("common.open_directory_failed", "ディレクトリを開けませんでした: {error}", "Could not open the directory: {error}"),
+    ("clipboard.open_failed", "クリップボードを開けませんでした: {error}", "Could not open the clipboard: {error}"),
+    ("clipboard.read_failed", "クリップボードの読み込みに失敗しました: {error}", "Failed to read the clipboard: {error}"),
+    ("tools.configured_missing", "設定された{name}が見つかりません: {path}", "The configured {name} was not found: {path}"),
+    ("title.not_found", "タイトルが取得できませんでした", "Could not get the title"),
+    // 設定
+    ("settings.serialize_failed", "設定のシリアライズに失敗しました: {error}", "Failed to serialize the settings: {error}"),
+    ("settings.save_failed", "設定の保存に失敗しました: {error}", "Failed to save the settings: {error}"),
+    ("settings.read_failed", "設定ファイルの読み込みに失敗しました: {error}", "Failed to read the settings file: {error}"),
+    ("settings.parse_failed", "設定のデシリアライズに失敗しました: {error}", "Failed to parse the settings: {error}"),
+    ("server.token_failed", "トークンを生成できませんでした: {error}", "Could not generate a token: {error}"),
+    ("server.listen_failed", "ポート{port}で待ち受けできませんでした: {error}", "Could not listen on port {port}: {error}"),
+    // ダウンロード
+    ("download.directory_created", "📁 保存先フォルダを作成しました: {path}", "📁 Created output folder: {path}"),
+    ("download.size_estimate", "💾 推定サイズ: {estimated} / 空き容量: {available}", "💾 Estimated size: {estimated} / Free space: {available}"),
+    ("download.completed", "✅ ダウンロードが完了しました", "✅ Download completed"),
+    ("download.finished", "✅ 正常に完了しました", "✅ Finished successfully"),
+    ("download.failed", "❌ ダウンロードに失敗しました", "❌ Download failed"),
+    ("download.stopped", "⏹️ ダウンロードを停止しました", "⏹️ Download stopped"),
+    ("download.reencode_failed", "❌ 再エンコード中にエラーが発生しました", "❌ An error occurred while re-encoding"),
+    ("download.retry_scheduled", "🔁 {seconds}秒後に再試行します（{attempt}/{max}回目）", "🔁 Retrying in {seconds}s (attempt {attempt}/{max})"),
+    ("download.loudness_applied", "🔊 ラウドネス処理: {lufs} LUFS → {gain} dB", "🔊 Loudness: {lufs} LUFS → {gain} dB"),
+    ("download.cover_saved", "🖼️ カバー画像を保存しました: {path}", "🖼️ Saved cover image: {path}"),
+    // ジョブ
+    ("job.invalid_id", "不正なジョブIDです: {job}", "Invalid job ID: {job}"),
+    ("job.not_found", "ジョブが見つかりません: {job}", "Job not found: {job}"),
+    ("job.not_running", "実行中のジョブが見つかりません: {job}", "No running job found: {job}"),
+    ("job.already_running", "既に実行中のジョブです: {job}", "The job is already running: {job}"),
+    ("job.stop_failed", "停止シグナルを送信できませんでした: {error}", "Could not send the stop signal: {error}"),
+    ("job.kill_failed", "プロセスを停止できませんでした: {error}", "Could not stop the process: {error}"),
+    ("job.serialize_failed", "ジョブのシリアライズに失敗しました: {error}", "Failed to serialize the job: {error}"),
+    ("job.state_save_failed", "ジョブの状態を保存できませんでした: {error}", "Could not save the job state: {error}"),
+    ("job.state_load_failed", "ジョブの状態を読み込めませんでした: {error}", "Could not read the job state: {error}"),
+    ("job.interrupted_not_found", "中断されたジョブが見つかりません: {job}", "Interrupted job not found: {job}"),
+    ("job.discard_running", "実行中のジョブは破棄できません: {job}", "A running job cannot be discarded: {job}"),
+    // ジョブのログ
+    (
+        "joblog.started",
+        "ジョブ開始: {url} (形式: {format}, 画質: {quality}, 保存先: {directory})",
+        "Job started: {url} (format: {format}, quality: {quality}, output: {directory})",
+    ),
+    ("joblog.failed", "ジョブ失敗: {error}", "Job failed: {error}"),
+    ("joblog.completed", "ジョブ完了: {count}件のファイル", "Job completed: {count} file(s)"),
+    ("joblog.saved", "保存: {path}", "Saved: {path}"),
+    ("joblog.stopped", "ジョブ停止", "Job stopped"),
+    ("joblog.exit_code", "終了コード: {code}", "Exit code: {code}"),
+    ("joblog.exit_code_stopped", "終了コード: {code}（停止操作）", "Exit code: {code} (stopped)"),
+    ("joblog.not_found", "ログが見つかりません: {job}", "Log not found: {job}"),
+    ("joblog.nothing_to_export", "書き出すログがありません", "There are no logs to export"),
+    ("joblog.export_failed", "ログを書き出せませんでした: {error}", "Could not export the logs: {error}"),
+    // 品質の制約（コマンドプレビュー）
+    ("constraint.max_height", "最大解像度: {height}p", "Max resolution: {height}p"),
+    ("constraint.max_fps", "最大フレームレート: {fps}fps", "Max frame rate: {fps}fps"),
+    ("constraint.min_fps", "最小フレームレート: {fps}fps", "Min frame rate: {fps}fps"),
+    ("constraint.prefer_60fps", "60fpsを優先", "Prefer 60fps"),
+    ("constraint.max_video_bitrate", "最大映像ビットレート: {bitrate}kbps", "Max video bitrate: {bitrate}kbps"),
+    ("constraint.max_filesize", "最大ファイルサイズ: {size}MB", "Max file size: {size}MB"),
+    // カバー画像・タグ
+    ("cover.convert_failed", "カバー画像の変換に失敗しました: {error}", "Failed to convert the cover image: {error}"),
+    ("cover.thumbnail_not_found", "サムネイルが見つかりませんでした", "No thumbnail was found"),
+    ("cover.save_failed", "カバー画像を保存できませんでした: {error}", "Could not save the cover image: {error}"),
+    ("cover.read_failed", "カバー画像を読み込めませんでした: {error}", "Could not read the cover image: {error}"),
+    ("tags.read_failed", "タグの読み込みに失敗しました: {error}", "Failed to read tags: {error}"),
+    ("tags.write_failed", "タグの書き込みに失敗しました: {error}", "Failed to write tags: {error}"),
+    ("tags.temp_failed", "タグの一時ファイルを作成できませんでした: {error}", "Could not create a temporary tag file: {error}"),
+    ("tags.unsupported_format", "タグ編集に対応していない形式です: {extension}", "Tag editing is not supported for this format: {extension}"),
+    ("tags.video_cover_unsupported", "動画ファイルのカバー画像の変更には対応していません", "Changing the cover image of video files is not supported"),
+    // ラウドネス処理
+    ("loudness.measure_failed", "ラウドネスの測定に失敗しました: {error}", "Failed to measure loudness: {error}"),
+    ("loudness.result_missing", "ラウドネスの測定結果を取得できませんでした", "Could not get the loudness measurement"),
+    ("loudness.invalid_value", "ラウドネスの測定値が不正です: {key}", "Invalid loudness measurement: {key}"),
+    ("loudness.unsupported_format", "ラウドネス処理に対応していない形式です: {extension}", "Loudness processing is not supported for this format: {extension}"),
+    ("loudness.unknown_mode", "不明なラウドネス処理です: {mode}", "Unknown loudness mode: {mode}"),
+    // 再エンコード
+    ("postprocess.preset.h264_crf", "H.264 (CRF 20)", "H.264 (CRF 20)"),
+    ("postprocess.preset.hevc", "HEVC (CRF 24)", "HEVC (CRF 24)"),
+    ("postprocess.preset.audio_normalize", "音量の正規化", "Normalize volume"),
+    ("postprocess.preset.device_iphone", "iPhone / iPad向け", "For iPhone / iPad"),
+    ("postprocess.preset.device_tv", "テレビ・古い機器向け (720p)", "For TVs and older devices (720p)"),
+    ("postprocess.unknown_preset", "不明な再エンコードプリセットです: {preset}", "Unknown re-encode preset: {preset}"),
+    ("postprocess.video_only", "「{preset}」は動画ファイル専用のプリセットです", "\"{preset}\" can only be used with video files"),
+    ("postprocess.started", "🎞️ 再エンコードを開始します: {preset}", "🎞️ Starting re-encode: {preset}"),
+    ("postprocess.failed", "❌ 再エンコードに失敗しました: {error}", "❌ Re-encode failed: {error}"),
+    ("postprocess.completed", "✅ 再エンコードが完了しました", "✅ Re-encode completed"),
+    ("postprocess.delete_original_failed", "元ファイルを削除できませんでした: {error}", "Could not delete the original file: {error}"),
+    // 同名ファイル
+    ("collision.ask", "⚠️ 同名のファイルが既に存在します", "⚠️ A file with the same name already exists"),
+    ("collision.skipped", "⏭️ 同名のファイルが既に存在するためスキップしました", "⏭️ Skipped because a file with the same name already exists"),
+    ("collision.overwriting", "♻️ 既存のファイルを上書きします（{count}件）", "♻️ Overwriting existing files ({count})"),
+    ("collision.renaming", "✏️ 同名のファイルがあるため「{suffix}」を付けて保存します", "✏️ A file with the same name exists; saving with \"{suffix}\" appended"),
+    ("collision.partially_skipped", "⏭️ 既に存在する{count}件をスキップします", "⏭️ Skipping {count} existing items"),
+    // ライブ録画
+    ("recording.progress", "🔴 録画中 {elapsed} / {size}", "🔴 Recording {elapsed} / {size}"),
+    ("recording.stopped", "⏹️ 録画を停止しました", "⏹️ Recording stopped"),
+    ("recording.max_duration", "⏹️ 録画時間の上限に達したため録画を停止します", "⏹️ Maximum recording time reached; stopping"),
+    // 保存先の確認
+    ("preflight.empty_directory", "保存先フォルダが指定されていません", "No output folder is specified"),
+    ("preflight.create_failed", "保存先フォルダを作成できませんでした: {path} ({reason})", "Could not create the output folder: {path} ({reason})"),
+    ("preflight.not_a_directory", "保存先がフォルダではありません: {path}", "The output location is not a folder: {path}"),
+    ("preflight.not_writable", "保存先フォルダに書き込めません: {path} ({reason})", "Cannot write to the output folder: {path} ({reason})"),
+    (
+        "preflight.insufficient_space",
+        "保存先の空き容量が不足しています: {path}（必要: {required}, 空き: {available}）",
+        "Not enough free space in {path} (required: {required}, available: {available})",
+    ),
+    // コマンドライン
+    ("cli.missing_url", "URLを指定してください", "Please specify a URL"),
+    ("cli.missing_value", "{option} には値が必要です", "{option} requires a value"),
+    ("cli.unknown_option", "不明なオプションです: {option}", "Unknown option: {option}"),
+    ("cli.invalid_quality", "{format} には使えない画質です: {value}（動画は 1080 など、音声は 320k など）", "Invalid quality for {format}: {value} (use e.g. 1080 for video, 320k for audio)"),
+    ("cli.invalid_browser", "対応していないブラウザです: {browser}（{supported}）", "Unsupported browser: {browser} ({supported})"),
+    ("cli.invalid_set", "--set は 項目名=値 の形式で指定してください: {value}", "--set must be in the form key=value: {value}"),
+    ("cli.usage", CLI_USAGE_JA, CLI_USAGE_EN),
+    // プリセット
+    ("preset.not_found", "プリセットが見つかりません: {preset}", "Preset not found: {preset}"),
+    ("preset.already_exists", "同じ名前のプリセットが既にあります: {preset}", "A preset with this name already exists: {preset}"),
+    ("preset.invalid_name", "プリセット名が正しくありません: {preset}", "Invalid preset name: {preset}"),
+    ("preset.invalid_file", "プリセットのファイルを読み込めませんでした: {error}", "Could not read the preset file: {error}"),
+    ("preset.serialize_failed", "プリセットのシリアライズに失敗しました: {error}", "Failed to serialize the presets: {error}"),
+    ("preset.save_failed", "プリセットの保存に失敗しました: {error}", "Failed to save the presets: {error}"),
+    // URLの判定
+    ("url.invalid", "URLを解釈できませんでした: {url}", "Could not read the URL: {url}"),
+    ("url.unsupported_youtube", "対応していないYouTubeのURLです: {url}", "Unsupported YouTube URL: {url}"),
+    ("url.invalid_video_id", "動画IDが正しくありません: {id}", "Invalid video ID: {id}"),
+    ("url.invalid_playlist_id", "プレイリストIDが正しくありません: {id}", "Invalid playlist ID: {id}"),
+    // URLの一括取り込み
+    ("batch.read_failed", "ファイルを読み込めませんでした: {path} ({error})", "Could not read the file: {path} ({error})"),
+    ("batch.file_too_large", "ファイルが大きすぎます: {path}", "The file is too large: {path}"),
+    ("batch.no_urls", "URLが見つかりませんでした", "No URLs were found"),
+    // necd://リンク
+    ("deeplink.invalid", "リンクを解釈できませんでした: {link}", "Could not read the link: {link}"),
+    ("deeplink.unsupported_action", "対応していないリンクです: {action}", "Unsupported link action: {action}"),
+    ("deeplink.missing_url", "リンクにダウンロードするURLがありません: {link}", "The link does not contain a URL to download: {link}"),
+    ("deeplink.invalid_url", "http(s)のURLではありません: {url}", "Not an http(s) URL: {url}"),
+    ("deeplink.invalid_value", "{name} の値が正しくありません: {value}", "Invalid value for {name}: {value}"),
+    ("deeplink.register_failed", "necd://の登録に失敗しました: {error}", "Failed to register necd://: {error}"),
+    // yt-dlpの管理
+    ("ytdlp.installed", "yt-dlp {version} のインストールが完了しました", "Installed yt-dlp {version}"),
+    ("ytdlp.state_serialize_failed", "状態のシリアライズに失敗しました: {error}", "Failed to serialize the state: {error}"),
+    ("ytdlp.state_save_failed", "状態の保存に失敗しました: {error}", "Failed to save the state: {error}"),
+    ("ytdlp.fetch_failed", "ダウンロードに失敗しました: {url} ({error})", "Download failed: {url} ({error})"),
+    ("ytdlp.version_check_failed", "ダウンロードしたyt-dlpのバージョンを確認できませんでした", "Could not check the version of the downloaded yt-dlp"),
+    (
+        "ytdlp.pinned",
+        "バージョン{pinned}に固定されているため{version}は使用できません（固定を解除してください）",
+        "Version {version} cannot be used because yt-dlp is pinned to {pinned} (unpin it first)",
+    ),
+    ("ytdlp.http_client_failed", "HTTPクライアントを作成できませんでした: {error}", "Could not create the HTTP client: {error}"),
+    ("ytdlp.checksum_missing", "チェックサムファイルに{asset}が含まれていません", "The checksum file does not include {asset}"),
+    ("ytdlp.checksum_mismatch", "チェックサムが一致しません（期待値: {expected}, 実際: {actual}）", "Checksum mismatch (expected: {expected}, actual: {actual})"),
+    ("ytdlp.save_failed", "yt-dlpを保存できませんでした: {error}", "Could not save yt-dlp: {error}"),
+    ("ytdlp.permission_failed", "実行権限を設定できませんでした: {error}", "Could not set the executable permission: {error}"),
+    ("ytdlp.place_failed", "yt-dlpを配置できませんでした: {error}", "Could not install yt-dlp: {error}"),
+    ("ytdlp.not_installed", "バージョン{version}はインストールされていません", "Version {version} is not installed"),
+    ("ytdlp.no_previous", "戻せる以前のバージョンがありません", "There is no previous version to roll back to"),
+    // 動作環境の診断
+    ("diagnostics.no_response", "応答がありません", "No response"),
+    ("diagnostics.exit_code", "終了コード {code}: {error}", "Exit code {code}: {error}"),
+    ("diagnostics.run_failed", "実行できませんでした: {path} ({error})", "Could not run: {path} ({error})"),
+    ("diagnostics.yt_dlp_age", "{version}（{days}日前のリリース）: {path}", "{version} (released {days} days ago): {path}"),
+    ("diagnostics.yt_dlp_outdated", "サイトの仕様変更に追従するため、yt-dlpを更新してください", "Update yt-dlp to keep up with site changes"),
+    ("diagnostics.yt_dlp_missing", "yt-dlpをインストールするか、設定でyt-dlpのパスを指定してください", "Install yt-dlp or set its path in the settings"),
+    ("diagnostics.ffmpeg_missing", "ffmpegをインストールするか、設定でffmpeg・ffprobeのパスを指定してください", "Install ffmpeg or set the ffmpeg and ffprobe paths in the settings"),
+    ("diagnostics.codec", "{format}エンコーダー ({encoder})", "{format} encoder ({encoder})"),
+    ("diagnostics.codec_skipped", "ffmpegを実行できないため確認していません", "Not checked because ffmpeg could not be run"),
+    ("diagnostics.codec_available", "利用できます", "Available"),
+    ("diagnostics.codec_missing", "{encoder}を含まないffmpegです", "This ffmpeg build does not include {encoder}"),
+    ("diagnostics.codec_suggestion", "libmp3lame・libopusを含むビルドのffmpegを使用してください", "Use an ffmpeg build that includes libmp3lame and libopus"),
+    ("diagnostics.output_directory", "保存先フォルダ", "Output folder"),
+    ("diagnostics.directory_unset", "保存先が設定されていません", "No output folder is set"),
+    ("diagnostics.directory_unset_suggestion", "設定で保存先フォルダを選択してください", "Choose an output folder in the settings"),
+    ("diagnostics.directory_missing", "フォルダが存在しません: {path}", "The folder does not exist: {path}"),
+    ("diagnostics.directory_missing_suggestion", "保存先フォルダを作成するか、別のフォルダを選択してください", "Create the output folder or choose another one"),
+    ("diagnostics.directory_writable", "書き込みできます: {path}", "Writable: {path}"),
+    ("diagnostics.directory_not_writable", "書き込みできません: {path} ({error})", "Not writable: {path} ({error})"),
+    ("diagnostics.directory_not_writable_suggestion", "フォルダのアクセス権を確認するか、別のフォルダを選択してください", "Check the folder permissions or choose another folder"),
+    ("diagnostics.free_space", "空き容量", "Free space"),
+    ("diagnostics.free_space_left", "残り {size} GB", "{size} GB left"),
+    ("diagnostics.free_space_low", "高画質の動画は数GBになることがあります。不要なファイルを削除してください", "High-quality videos can take several GB. Delete files you no longer need"),
+    ("diagnostics.free_space_unavailable", "取得できませんでした: {error}", "Could not check: {error}"),
+    ("diagnostics.cookie_file", "Cookieファイル", "Cookie file"),
+    ("diagnostics.cookie_suggestion", "ブラウザの拡張機能などでNetscape形式のCookieファイルを書き出し直してください", "Export the cookies again in Netscape format, for example with a browser extension"),
+    ("diagnostics.cookie_unreadable", "読み込めません: {path} ({error})", "Cannot read: {path} ({error})"),
+    ("diagnostics.cookie_none", "Netscape形式のCookieが見つかりません: {path}", "No Netscape-format cookies found: {path}"),
+    ("diagnostics.cookie_expired", "すべてのCookieの有効期限が切れています（{total}件）", "All cookies have expired ({total})"),
+    ("diagnostics.cookie_valid", "有効なCookie {valid}件 / {total}件", "{valid} of {total} cookies are valid"),
+    ("diagnostics.proxy", "プロキシ", "Proxy"),
+    ("diagnostics.proxy_suggestion", "プロキシのアドレスとポート、プロキシが起動しているかを確認してください", "Check the proxy address and port, and that the proxy is running"),
+    ("diagnostics.proxy_connected", "接続できます: {address}", "Connected: {address}"),
+    ("diagnostics.proxy_failed", "接続できません: {address} ({error})", "Cannot connect: {address} ({error})"),
+    ("diagnostics.proxy_timeout", "接続がタイムアウトしました: {address}", "Connection timed out: {address}"),
+    // yt-dlpのエラー
+    ("error.private_video", "非公開の動画です。視聴権限のあるアカウントのCookieを設定してください", "This video is private. Set cookies from an account that can view it"),
+    ("error.members_only", "メンバー限定の動画です。メンバーのアカウントのCookieを設定してください", "This video is members-only. Set cookies from a member account"),
+    (
+        "error.login_required",
+        "ログインが必要です（年齢制限・ボット確認など）。ブラウザのCookieを設定してください",
+        "Sign-in is required (age restriction, bot check, etc.). Set cookies from your browser",
+    ),
+    ("error.geo_blocked", "お住まいの地域では視聴できない動画です", "This video is not available in your region"),
+    ("error.removed", "動画が削除されたか、存在しません", "The video has been removed or does not exist"),
+    ("error.http403", "アクセスが拒否されました (HTTP 403)。時間をおくか、yt-dlpを更新してください", "Access was denied (HTTP 403). Wait a while or update yt-dlp"),
+    ("error.http429", "アクセスが集中しています (HTTP 429)。しばらく待ってから再試行してください", "Too many requests (HTTP 429). Wait a while and try again"),
+    ("error.ffmpeg_missing", "ffmpegが見つかりません。ffmpegをインストールするか、設定でパスを指定してください", "ffmpeg was not found. Install ffmpeg or set its path in the settings"),
+    ("error.unsupported_url", "対応していないURLです", "This URL is not supported"),
+    ("error.disk_full", "保存先の空き容量が不足しています", "The output drive is out of space"),
+    ("error.extractor_outdated", "サイトの仕様が変わった可能性があります。yt-dlpを更新してください", "The site may have changed. Update yt-dlp"),
+    ("error.network", "通信エラーが発生しました。接続を確認して再試行してください", "A network error occurred. Check your connection and try again"),
+    ("error.unknown", "エラーが発生しました", "An error occurred"),
+];
+
+const CLI_USAGE_JA: &str = "使い方: necd-tauri download <URL>... [オプション]
+
+保存された設定とプリセットを元に、指定したオプションで上書きしてダウンロードします。
+
+オプション:
+      --preset <名前>          保存したプリセット（省略すると既定のプリセット）
+  -f, --format <形式>          mp4, mkv, webm, mp3, m4a, opus, flac など
+  -q, --quality <画質>         動画は auto, 2160, 1080, 720 など（1080p も可）
+                               音声は auto, 320k, 192k など（320 も可）
+  -o, --out <フォルダ>         保存先フォルダ
+      --playlist               プレイリスト全体をダウンロード
+      --no-playlist            動画のみをダウンロード
+      --cookies <ファイル>     Netscape形式のCookieファイル
+      --cookies-from-browser <ブラウザ>
+                               firefox, chrome, edge, safari など
+      --embed-thumbnail        サムネイルを埋め込む
+      --embed-chapters         チャプターを埋め込む
+      --music                  音楽モード
+      --collision <方針>       skip, overwrite, rename, ask
+      --reencode <プリセット>  再エンコードのプリセット
+      --loudness <方式>        none, loudnorm, replaygain
+      --live <方式>            none, from_start, from_now
+      --live-max-duration <秒>
+      --connections <数>
+      --retries <回数>
+      --max-attempts <回数>
+      --set <項目名=値>        設定ファイルと同じ名前で任意の項目を指定（例: --set maxFps=30）
+      --quiet                  エラー以外を表示しない
+      --json                   結果をJSONで標準出力に書き出す
+
+終了コード:
+  0 成功 / 1 失敗 / 2 引数の誤り / 3 保存先の問題 / 4 同名ファイルの確認が必要 / 5 開始できない";
+
+const CLI_USAGE_EN: &str = "Usage: necd-tauri download <URL>... [options]
+
+Downloads using the saved settings and preset, overridden by the given options.
+
+Options:
+      --preset <name>          Saved preset (defaults to the default preset)
+  -f, --format <format>        mp4, mkv, webm, mp3, m4a, opus, flac, ...
+  -q, --quality <quality>      Video: auto, 2160, 1080, 720, ... (1080p also accepted)
+                               Audio: auto, 320k, 192k, ... (320 also accepted)
+  -o, --out <dir>              Output folder
+      --playlist               Download the whole playlist
+      --no-playlist            Download only the video
+      --cookies <file>         Netscape-format cookie file
+      --cookies-from-browser <browser>
+                               firefox, chrome, edge, safari, ...
+      --embed-thumbnail        Embed the thumbnail
+      --embed-chapters         Embed chapters
+      --music                  Music mode
+      --collision <policy>     skip, overwrite, rename, ask
+      --reencode <preset>      Re-encode preset
+      --loudness <mode>        none, loudnorm, replaygain
+      --live <mode>            none, from_start, from_now
+      --live-max-duration <seconds>
+      --connections <n>
+      --retries <n>
+      --max-attempts <n>
+      --set <key=value>        Set any option by its settings name (e.g. --set maxFps=30)
+      --quiet                  Only print errors
+      --json                   Print the result as JSON to stdout
+
+Exit codes:
+  0 success / 1 failed / 2 usage error / 3 output folder problem / 4 existing file needs confirmation / 5 could not start";
+
+// 設定またはOSから決めた言語（設定の保存時に破棄する）
+static LOCALE: Mutex<Option<Locale>> = Mutex::new(None);
+
+impl Locale {
+    pub fn parse(value: &str) -> Option<Locale> {
+        let lower = value.trim().to_lowercase();
+        if lower.starts_with("ja") {
+            Some(Locale::Ja)
+        } else if lower.starts_with("en") {
+            Some(Locale::En)
+        } else {
+            None
+        }
+    }
+}
+
+// 設定の"language"（"auto"または未設定ならOSの言語、日本語以外は英語）
+fn detect() -> Locale {
+    let configured = dirs::config_dir()
+        .map(|dir| dir.join("necd-tauri").join("settings.json"))
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|settings| settings.get("language").and_then(|value| value.as_str()).and_then(Locale::parse));
+    configured.unwrap_or_else(|| match sys_locale::get_locale().as_deref().and_then(Locale::parse) {
+        Some(Locale::Ja) => Locale::Ja,
+        _ => Locale::En,
+    })
+}
+
+pub fn current() -> Locale {
+    let mut locale = LOCALE.lock().unwrap_or_else(|e| e.into_inner());
+    *locale.get_or_insert_with(detect)
+}
+
+pub fn reset() {
+    *LOCALE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn template(key: &str, locale: Locale) -> Option<&'static str> {
+    CATALOG.iter().find(|(k, _, _)| *k == key).map(|(_, ja, en)| match locale {
+        Locale::Ja => *ja,
+        Locale::En => *en,
+    })
+}
+
+impl Message {
+    pub fn new(key: &str) -> Message {
+        Message {
+            key: key.to_string(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    pub fn arg(mut self, name: &str, value: impl ToString) -> Message {
+        self.params.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub fn render(&self, locale: Locale) -> String {
+        let mut text = template(&self.key, locale).unwrap_or(&self.key).to_string();
+        for (name, value) in &self.params {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+
+    // 現在の言語で表示する
+    pub fn text(&self) -> String {
+        self.render(current())
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text())
+    }
+}
+
+// 外部のライブラリやコマンドから受け取った翻訳の無いエラー
+impl From<String> for Message {
+    fn from(detail: String) -> Message {
+        Message::new("common.detail").arg("detail", detail)
+    }
+}
+
+impl From<&str> for Message {
+    fn from(detail: &str) -> Message {
+        Message::new("common.detail").arg("detail", detail)
+    }
+}
+
+pub fn catalog(locale: Locale) -> Catalog {
+    Catalog {
+        locale,
+        messages: CATALOG
+            .iter()
+            .map(|(key, _, _)| (key.to_string(), template(key, locale).unwrap_or_default().to_string()))
+            .collect(),
+    }
+}
@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use crate::i18n::Message;
+use crate::jobs;
+use crate::tools;
+
+// 1ファイルの上限。超えたら<ジョブID>.1.logに移して書き直す
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+// 設定が無い場合の保持期間と合計サイズの上限
+const DEFAULT_RETENTION_DAYS: u64 = 14;
+const DEFAULT_MAX_TOTAL_MB: u64 = 200;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobLogInfo {
+    pub job_id: String,
+    // 最終更新時刻 (UNIX秒)
+    pub modified_at: u64,
+    // ローテーションしたファイルを含むサイズ
+    pub size_bytes: u64,
+}
+
+// ジョブの実行ログ（複数のタスクから書き込めるよう共有する）
+#[derive(Clone)]
+pub struct JobLog {
+    inner: Arc<Mutex<Option<Writer>>>,
+}
+
+struct Writer {
+    job_id: String,
+    file: File,
+    written: u64,
+}
+
+fn logs_dir() -> Result<PathBuf, Message> {
+    Ok(dirs::data_dir()
+        .ok_or_else(|| Message::new("common.data_dir_unavailable"))?
+        .join("necd-tauri")
+        .join("logs"))
+}
+
+fn log_path(job_id: &str) -> Result<PathBuf, Message> {
+    // ジョブIDはファイル名に使うため区切り文字を含めない
+    if job_id.is_empty() || job_id.contains(['/', '\\']) || job_id.contains("..") {
+        return Err(Message::new("job.invalid_id").arg("job", job_id));
+    }
+    Ok(logs_dir()?.join(format!("{}.log", job_id)))
+}
+
+fn rotated_path(job_id: &str) -> Result<PathBuf, Message> {
+    Ok(logs_dir()?.join(format!("{}.1.log", job_id)))
+}
+
+// 1970-01-01からの日数を年月日に変換する（グレゴリオ暦）
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// 年月日を1970-01-01からの日数に変換する（civil_from_daysの逆）
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+// ログの各行に付ける時刻 (UTC)
+fn timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+    let rem = secs.rem_euclid(86400);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+// 設定ファイルの数値（logRetentionDays, logMaxTotalMb）
+fn setting(key: &str, default: u64) -> u64 {
+    dirs::config_dir()
+        .map(|dir| dir.join("necd-tauri").join("settings.json"))
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|settings| settings.get(key).and_then(|value| value.as_u64()))
+        .unwrap_or(default)
+}
+
+fn open_file(path: &PathBuf) -> Option<File> {
+    OpenOptions::new().create(true).append(true).open(path).ok()
+}
+
+impl JobLog {
+    // ログを開く（再実行・再開時は同じファイルに追記する）。開けなくてもダウンロードは続ける
+    pub fn open(job_id: &str) -> JobLog {
+        prune();
+        let writer = log_path(job_id).ok().and_then(|path| {
+            fs::create_dir_all(logs_dir().ok()?).ok()?;
+            let file = open_file(&path)?;
+            let written = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+            Some(Writer {
+                job_id: job_id.to_string(),
+                file,
+                written,
+            })
+        });
+        JobLog {
+            inner: Arc::new(Mutex::new(writer)),
+        }
+    }
+
+    // streamは"stdout", "stderr", "app"のいずれか
+    pub fn line(&self, stream: &str, message: &str) {
+        let mut guard = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(writer) = guard.as_mut() else {
+            return;
+        };
+        let entry = format!("{} [{}] {}\n", timestamp(), stream, message);
+        if writer.written + entry.len() as u64 > MAX_FILE_BYTES {
+            writer.rotate();
+        }
+        if writer.file.write_all(entry.as_bytes()).is_ok() {
+            writer.written += entry.len() as u64;
+        }
+    }
+}
+
+impl Writer {
+    fn rotate(&mut self) {
+        let (Ok(path), Ok(rotated)) = (log_path(&self.job_id), rotated_path(&self.job_id)) else {
+            return;
+        };
+        let _ = self.file.flush();
+        if fs::rename(&path, &rotated).is_err() {
+            return;
+        }
+        if let Some(file) = open_file(&path) {
+            self.file = file;
+            self.written = 0;
+        }
+    }
+}
+
+// ジョブID、最終更新時刻、パス（.1.logは元のジョブにまとめる）
+fn entries() -> Vec<(String, SystemTime, PathBuf, u64)> {
+    let Some(read_dir) = logs_dir().ok().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    read_dir
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let stem = name.strip_suffix(".log")?;
+            let job_id = stem.strip_suffix(".1").unwrap_or(stem).to_string();
+            let meta = entry.metadata().ok()?;
+            Some((job_id, meta.modified().unwrap_or(SystemTime::UNIX_EPOCH), entry.path(), meta.len()))
+        })
+        .collect()
+}
+
+// 保持期間を過ぎたログを削除し、合計サイズが上限を超えていれば古いものから削除する
+pub fn prune() {
+    let retention = Duration::from_secs(setting("logRetentionDays", DEFAULT_RETENTION_DAYS) * 86400);
+    let max_total = setting("logMaxTotalMb", DEFAULT_MAX_TOTAL_MB) * 1024 * 1024;
+    let now = SystemTime::now();
+
+    let mut files = entries();
+    // 実行中のジョブのログは残す
+    files.retain(|(job_id, _, _, _)| !jobs::is_running(job_id));
+    files.sort_by_key(|(_, modified, _, _)| *modified);
+
+    let mut total: u64 = files.iter().map(|(_, _, _, size)| size).sum();
+    for (_, modified, path, size) in files {
+        let expired = now.duration_since(modified).is_ok_and(|age| age > retention);
+        if (expired || total > max_total) && fs::remove_file(&path).is_ok() {
+            total = total.saturating_sub(size);
+        }
+    }
+}
+
+pub fn list() -> Vec<JobLogInfo> {
+    let mut logs: Vec<JobLogInfo> = Vec::new();
+    for (job_id, modified, _, size) in entries() {
+        let modified_at = modified
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        match logs.iter_mut().find(|log| log.job_id == job_id) {
+            Some(log) => {
+                log.size_bytes += size;
+                log.modified_at = log.modified_at.max(modified_at);
+            }
+            None => logs.push(JobLogInfo {
+                job_id,
+                modified_at,
+                size_bytes: size,
+            }),
+        }
+    }
+    // 新しいものから
+    logs.sort_by_key(|log| std::cmp::Reverse(log.modified_at));
+    logs
+}
+
+// ジョブのログ全体（ローテーションした古い部分を先に）
+pub fn read(job_id: &str) -> Result<String, Message> {
+    let path = log_path(job_id)?;
+    let rotated = fs::read_to_string(rotated_path(job_id)?).unwrap_or_default();
+    match fs::read_to_string(&path) {
+        Ok(content) => Ok(rotated + &content),
+        Err(_) if !rotated.is_empty() => Ok(rotated),
+        Err(_) => Err(Message::new("joblog.not_found").arg("job", job_id)),
+    }
+}
+
+// 不具合報告用に環境情報とログを1つのテキストファイルにまとめる（job_idsが空なら全て）
+pub fn export(destination: &str, job_ids: &[String]) -> Result<String, Message> {
+    let tool_paths = tools::all();
+    let mut bundle = String::new();
+    bundle.push_str("# necd-tauri log bundle\n");
+    bundle.push_str(&format!("created: {}\n", timestamp()));
+    bundle.push_str(&format!("version: {}\n", env!("CARGO_PKG_VERSION")));
+    bundle.push_str(&format!("os: {} {}\n", std::env::consts::OS, std::env::consts::ARCH));
+    for tool in [&tool_paths.yt_dlp, &tool_paths.ffmpeg, &tool_paths.ffprobe] {
+        bundle.push_str(&format!("{}: {}{}\n", tool.name, tool.resolved, if tool.found { "" } else { " (not found)" }));
+    }
+
+    let logs: Vec<JobLogInfo> = list()
+        .into_iter()
+        .filter(|log| job_ids.is_empty() || job_ids.contains(&log.job_id))
+        .collect();
+    if logs.is_empty() {
+        return Err(Message::new("joblog.nothing_to_export"));
+    }
+    for log in &logs {
+        bundle.push_str(&format!("\n===== {} =====\n", log.job_id));
+        bundle.push_str(&read(&log.job_id).unwrap_or_default());
+    }
+
+    fs::write(destination, bundle).map_err(|e| Message::new("joblog.export_failed").arg("error", e))?;
+    Ok(destination.to_string())
+}
@@ -0,0 +1,181 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::i18n::Message;
+
+// 実行中のダウンロード（yt-dlpプロセス）
+struct RunningJob {
+    id: String,
+    pid: u32,
+    stop_requested: bool,
+}
+
+static RUNNING: Mutex<Vec<RunningJob>> = Mutex::new(Vec::new());
+// yt-dlpの起動前（確認中や再試行の待機中）に停止を求められたジョブ
+static STOP_BEFORE_START: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// ジョブIDが指定されなかった場合のID（同じミリ秒に複数追加しても重複しないよう連番を付ける）
+pub fn generate_id() -> String {
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+    format!(
+        "job-{}-{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default(),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+pub fn register(id: &str, pid: u32) {
+    let stop = {
+        let mut pending = STOP_BEFORE_START.lock().unwrap_or_else(|e| e.into_inner());
+        let stop = pending.iter().any(|pending_id| pending_id == id);
+        pending.retain(|pending_id| pending_id != id);
+        stop
+    };
+    {
+        let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+        running.retain(|job| job.id != id);
+        running.push(RunningJob {
+            id: id.to_string(),
+            pid,
+            stop_requested: stop,
+        });
+    }
+    if stop {
+        let _ = interrupt(pid);
+    }
+}
+
+// 実行中であれば停止し、yt-dlpの起動前であれば起動した時点で停止する
+pub fn request_stop_or_defer(id: &str) {
+    if request_stop(id).is_err() {
+        let mut pending = STOP_BEFORE_START.lock().unwrap_or_else(|e| e.into_inner());
+        if !pending.iter().any(|pending_id| pending_id == id) {
+            pending.push(id.to_string());
+        }
+    }
+}
+
+// 終了したジョブの保留中の停止要求を破棄する
+pub fn clear_deferred_stop(id: &str) {
+    STOP_BEFORE_START.lock().unwrap_or_else(|e| e.into_inner()).retain(|pending_id| pending_id != id);
+}
+
+pub fn unregister(id: &str) {
+    RUNNING.lock().unwrap_or_else(|e| e.into_inner()).retain(|job| job.id != id);
+}
+
+pub fn is_running(id: &str) -> bool {
+    RUNNING.lock().unwrap_or_else(|e| e.into_inner()).iter().any(|job| job.id == id)
+}
+
+pub fn stop_requested(id: &str) -> bool {
+    RUNNING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .any(|job| job.id == id && job.stop_requested)
+}
+
+// yt-dlpに割り込みを送り、録画中のファイルを確定させてから終了させる
+pub fn request_stop(id: &str) -> Result<(), Message> {
+    let pid = {
+        let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+        let job = running
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or_else(|| Message::new("job.not_running").arg("job", id))?;
+        if job.stop_requested {
+            return Ok(());
+        }
+        job.stop_requested = true;
+        job.pid
+    };
+    interrupt(pid)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn interrupt(pid: u32) -> Result<(), Message> {
+    // SIGINTを受けたyt-dlpはffmpegに終了を伝え、ライブ配信の録画を正常終了として扱う
+    let status = std::process::Command::new("kill")
+        .args(["-INT", &pid.to_string()])
+        .status()
+        .map_err(|e| Message::new("job.stop_failed").arg("error", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Message::new("job.stop_failed").arg("error", status))
+    }
+}
+
+// Windowsでは停止の合図を送ってもyt-dlpが終了しない場合に強制終了するまでの時間
+#[cfg(target_os = "windows")]
+const FORCE_KILL_AFTER: std::time::Duration = std::time::Duration::from_secs(15);
+
+#[cfg(target_os = "windows")]
+mod console {
+    pub const CTRL_BREAK_EVENT: u32 = 1;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn AttachConsole(process_id: u32) -> i32;
+        pub fn FreeConsole() -> i32;
+        pub fn SetConsoleCtrlHandler(handler: Option<unsafe extern "system" fn(u32) -> i32>, add: i32) -> i32;
+        pub fn GenerateConsoleCtrlEvent(ctrl_event: u32, process_group_id: u32) -> i32;
+    }
+}
+
+// yt-dlpはCREATE_NEW_PROCESS_GROUPで起動しているため、プロセスグループ（ID = PID）にCtrl+Breakを送れる
+// 合図を送るにはyt-dlpのコンソールに接続する必要があり、接続中は自分が終了しないようCtrlイベントを無視する
+#[cfg(target_os = "windows")]
+fn send_ctrl_break(pid: u32) -> bool {
+    // コンソールにはプロセスごとに1つしか接続できない
+    static CONSOLE: Mutex<()> = Mutex::new(());
+    let _guard = CONSOLE.lock().unwrap_or_else(|e| e.into_inner());
+    unsafe {
+        if console::AttachConsole(pid) == 0 {
+            return false;
+        }
+        console::SetConsoleCtrlHandler(None, 1);
+        let sent = console::GenerateConsoleCtrlEvent(console::CTRL_BREAK_EVENT, pid) != 0;
+        console::FreeConsole();
+        // イベントが届く前にハンドラを戻すと自分も終了するため少し待つ
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        console::SetConsoleCtrlHandler(None, 0);
+        sent
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn force_kill(pid: u32) -> Result<(), Message> {
+    use std::os::windows::process::CommandExt;
+    let status = std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .status()
+        .map_err(|e| Message::new("job.kill_failed").arg("error", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Message::new("job.kill_failed").arg("error", status))
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn interrupt(pid: u32) -> Result<(), Message> {
+    // Ctrl+Breakを受けたyt-dlpとffmpegは録画中のファイルを確定してから終了する
+    if !send_ctrl_break(pid) {
+        return force_kill(pid);
+    }
+    // 一定時間内に終了しなければ強制終了する（MPEG-TSで書き出した録画は途中までのファイルも再生できる）
+    std::thread::spawn(move || {
+        std::thread::sleep(FORCE_KILL_AFTER);
+        let still_running = RUNNING.lock().unwrap_or_else(|e| e.into_inner()).iter().any(|job| job.pid == pid);
+        if still_running {
+            let _ = force_kill(pid);
+        }
+    });
+    Ok(())
+}
@@ -1,21 +1,52 @@
+// 設定のデフォルト値のjson!マクロが大きいため
+#![recursion_limit = "256"]
+
 use serde::{Deserialize, Serialize};
-use std::process::Command;
 use std::path::PathBuf;
 use std::env;
 use std::fs;
-use tokio::process::Command as TokioCommand;
 use tokio::io::{AsyncBufReadExt, BufReader};
-use tauri::Emitter;
 use tauri_plugin_notification;
 use serde_json::Value;
 
+use events::EventSink;
+
+mod args;
+mod batch;
+pub mod cli;
+mod clipboard;
+mod collision;
+mod cover;
+mod deeplink;
+mod diagnostics;
+mod errors;
+mod events;
+mod i18n;
+mod joblog;
+mod jobs;
+mod live;
+mod loudness;
+mod music;
+mod postprocess;
+mod preflight;
+mod presets;
+mod queue;
+mod resume;
+mod retry;
+mod server;
+mod settings;
+mod tags;
+mod tools;
+mod urls;
+mod ytdlp;
+
 // バージョン情報を取得するコマンド
 #[tauri::command]
 fn get_app_version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DownloadOptions {
     pub url: String,
     pub format: String,
@@ -30,6 +61,88 @@ pub struct DownloadOptions {
     pub chapter_embed: bool,
     pub compatibility_mode: bool,
     pub hdr_mode: bool,
+    // 映像コーデックの優先順 ("av1", "vp9", "hevc", "h264")
+    #[serde(default)]
+    pub video_codec_preference: Vec<String>,
+    // 音声コーデックの優先順 ("opus", "aac", "vorbis", "mp3", "flac")
+    #[serde(default)]
+    pub audio_codec_preference: Vec<String>,
+    #[serde(default)]
+    pub max_fps: Option<u32>,
+    #[serde(default)]
+    pub min_fps: Option<u32>,
+    // フロントエンドと設定ファイルのキーはprefer60fps（toSnakeCaseは数字の前に_を付けない）
+    #[serde(default, alias = "prefer60fps")]
+    pub prefer_60fps: bool,
+    // 映像ビットレートの上限 (kbps)
+    #[serde(default)]
+    pub max_video_bitrate: Option<u32>,
+    // ファイルサイズの上限 (MB)
+    #[serde(default)]
+    pub max_filesize_mb: Option<u64>,
+    // ダウンロード後に適用する再エンコードプリセット
+    #[serde(default)]
+    pub reencode_preset: Option<String>,
+    // 再エンコード後に元ファイルを削除する
+    #[serde(default)]
+    pub delete_original: bool,
+    // 音声形式のラウドネス処理 ("none", "loudnorm", "replaygain")
+    #[serde(default)]
+    pub loudness_mode: String,
+    // loudnormの目標ラウドネス (LUFS)
+    #[serde(default)]
+    pub loudness_target: Option<f64>,
+    // 音声形式でアーティスト・タイトル・アルバム情報を整えるミュージックモード
+    #[serde(default)]
+    pub music_mode: bool,
+    // ミュージックモードの変換ルール（空なら既定のルール）
+    #[serde(default)]
+    pub metadata_rules: Vec<music::MetadataRule>,
+    // カバー画像のクロップ ("none", "square", "letterbox")。空ならthumbnail_cropに従う
+    #[serde(default)]
+    pub cover_crop: String,
+    // カバー画像の長辺サイズ (px)
+    #[serde(default)]
+    pub cover_size: Option<u32>,
+    // カバー画像の形式 ("jpg", "png", "webp")
+    #[serde(default)]
+    pub cover_format: String,
+    // 保存先フォルダにcover.*を保存する（プレイリストモードのみ。単体のダウンロードでは前のcover.*を上書きしてしまうため）
+    #[serde(default)]
+    pub save_cover_file: bool,
+    // ライブ配信の録画 ("none", "from_start"（配信開始時点から）, "from_now"（現在から）)
+    #[serde(default)]
+    pub live_mode: String,
+    // 録画時間の上限（秒）
+    #[serde(default)]
+    pub live_max_duration: Option<u64>,
+    // 配信予定の枠は開始まで待機する
+    #[serde(default)]
+    pub wait_for_video: bool,
+    // 配信開始を確認する間隔（秒）
+    #[serde(default)]
+    pub wait_interval: Option<u64>,
+    // 停止などの操作に使うジョブID（未指定なら自動で割り当てる）
+    #[serde(default)]
+    pub job_id: Option<String>,
+    // 同名ファイルがある場合 ("skip", "overwrite", "rename", "ask")
+    #[serde(default)]
+    pub collision_policy: String,
+    // 中断されたジョブの再開（途中ファイルから続ける）
+    #[serde(default)]
+    pub resume: bool,
+    // yt-dlpの--retries
+    #[serde(default)]
+    pub retries: Option<u32>,
+    // yt-dlpの--fragment-retries
+    #[serde(default)]
+    pub fragment_retries: Option<u32>,
+    // 一時的なエラーで失敗した場合にアプリ側で実行する最大回数（初回を含む）
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+    // 最初の再実行までの待ち時間（秒）。以降は倍々に延ばす
+    #[serde(default)]
+    pub retry_backoff_seconds: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,6 +150,30 @@ pub struct DownloadProgress {
     pub message: String,
     pub progress: Option<f64>,
     pub is_error: bool,
+    // 保存されたファイル（後処理後のパス）
+    #[serde(default)]
+    pub files: Vec<String>,
+    // ラウドネス処理の結果
+    #[serde(default)]
+    pub loudness: Vec<loudness::LoudnessResult>,
+    // 録画が停止操作または時間の上限で終了した
+    #[serde(default)]
+    pub stopped: bool,
+    // ダウンロード前の確認で中止した場合の理由
+    #[serde(default)]
+    pub preflight_error: Option<preflight::PreflightError>,
+    // 同名ファイルの扱い
+    #[serde(default)]
+    pub collision: Option<collision::CollisionReport>,
+    // yt-dlpのERROR:行を分類したもの
+    #[serde(default)]
+    pub errors: Vec<errors::DownloadError>,
+    // 実行ごとの結果（再実行の履歴）
+    #[serde(default)]
+    pub attempts: Vec<retry::AttemptRecord>,
+    // messageのキー
+    #[serde(default)]
+    pub i18n: Option<i18n::Message>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -44,6 +181,27 @@ pub struct RealTimeLog {
     pub message: String,
     pub is_error: bool,
     pub progress: Option<f64>,
+    // アプリが出したメッセージのキー（yt-dlpの出力はNone）
+    #[serde(default)]
+    pub i18n: Option<i18n::Message>,
+}
+
+impl RealTimeLog {
+    fn localized(message: i18n::Message, is_error: bool, progress: Option<f64>) -> RealTimeLog {
+        RealTimeLog {
+            message: message.text(),
+            is_error,
+            progress,
+            i18n: Some(message),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommandPreview {
+    pub command: String,
+    pub args: Vec<String>,
+    pub constraints: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -52,135 +210,59 @@ pub struct FileFilter {
     pub extensions: Vec<String>,
 }
 
-#[tauri::command]
-async fn execute_download(window: tauri::Window, options: DownloadOptions) -> Result<DownloadProgress, String> {
-    // Windows環境での文字エンコーディング設定
-    #[cfg(target_os = "windows")]
-    {
-        env::set_var("PYTHONIOENCODING", "utf-8");
-        env::set_var("PYTHONLEGACYWINDOWSSTDIO", "utf-8");
-        env::set_var("PYTHONUTF8", "1");
-    }
-    
-    // yt-dlpのパスを動的に取得
-    let yt_dlp_path = get_yt_dlp_path().await?;
-    let mut command = TokioCommand::new(&yt_dlp_path);
+// yt-dlpを1回実行した結果
+struct YtDlpRun {
+    success: bool,
+    exit_code: Option<i32>,
+    stopped: bool,
+    files: Vec<String>,
+    thumbnails: Vec<Option<Value>>,
+    errors: Vec<errors::DownloadError>,
+}
+
+// yt-dlpを実行し、ログを送りながら終了を待つ
+async fn run_yt_dlp<E: EventSink>(
+    events: &E,
+    yt_dlp_path: &str,
+    options: &DownloadOptions,
+    extra_args: &[String],
+    job_id: &str,
+    log: &joblog::JobLog,
+) -> Result<YtDlpRun, i18n::Message> {
+    // GUIから起動した場合はPATHが最小限のため、pyenvやHomebrewなどの場所も探せるようにする
+    let mut command = tools::command(yt_dlp_path);
     #[cfg(target_os = "windows")]
     {
-        use std::os::windows::process::CommandExt;
-        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
-    }
-    
-    // macOSの場合、pyenvのパスを追加
-    #[cfg(target_os = "macos")]
-    {
-        let current_path = env::var("PATH").unwrap_or_default();
-        let pyenv_path = format!("{}:/Users/mimi/.pyenv/shims:/opt/homebrew/bin", current_path);
-        command.env("PATH", pyenv_path);
-    }
-    
-    // 基本オプション
-    command.args(&[
-        "--newline",
-        &options.url,
-        "--embed-metadata",
-        "--add-metadata",
-        "--default-search", "ytsearch",
-        "--progress-template", "[DOWNLOADING]:%(progress._percent_str)s",
-        "--add-header", "Accept-Language:ja-JP",
-        "--extractor-args", "youtube:lang=ja",
-        "--no-warnings",
-        "--encoding", "utf-8"
-    ]);
-
-    // Cookie設定
-    match options.cookie_source.as_str() {
-        "file" => {
-            if let Some(cookie_path) = options.cookie_file_path {
-                command.args(&["--cookies", &cookie_path]);
-            }
-        }
-        "firefox" => {
-            command.args(&["--cookies-from-browser", "firefox"]);
-        }
-        _ => {}
-    }
-
-    // 出力形式と品質設定
-    match options.format.as_str() {
-        "mp4" => {
-            command.args(&["--merge-output-format", "mp4"]);
-            if options.quality != "auto" {
-                if options.compatibility_mode {
-                    command.args(&["-f", &format!("bestvideo[height<={}][vcodec^=avc1]+bestaudio[ext=m4a]/best[height<={}][vcodec^=avc1]/best[height<={}]", options.quality, options.quality, options.quality)]);
-                } else {
-                    command.args(&["-f", &format!("bestvideo[height<={}]+bestaudio[ext=m4a]/best[height<={}]", options.quality, options.quality)]);
-                }
-            } else {
-                if options.compatibility_mode {
-                    command.args(&["-f", "bestvideo[vcodec^=avc1]+bestaudio[ext=m4a]/best[vcodec^=avc1]/best"]);
-                } else {
-                    command.args(&["-f", "bestvideo+bestaudio[ext=m4a]/best"]);
-                }
-            }
-        }
-        "mkv" => {
-            command.args(&["--merge-output-format", "mkv"]);
-            if options.quality != "auto" {
-                if options.compatibility_mode {
-                    command.args(&["-f", &format!("bestvideo[height<={}][vcodec^=avc1]+bestaudio[ext=m4a]/best[height<={}][vcodec^=avc1]/best[height<={}]", options.quality, options.quality, options.quality)]);
-                } else {
-                    command.args(&["-f", &format!("bestvideo[height<={}]+bestaudio[ext=m4a]/best[height<={}]", options.quality, options.quality)]);
-                }
-            } else {
-                if options.compatibility_mode {
-                    command.args(&["-f", "bestvideo[vcodec^=avc1]+bestaudio[ext=m4a]/best[vcodec^=avc1]/best"]);
-                } else {
-                    command.args(&["-f", "bestvideo+bestaudio[ext=m4a]/best"]);
-                }
-            }
-        }
-        "mp3" => {
-            command.args(&["-f", "bestaudio", "-x", "--audio-format", "mp3"]);
-            if options.quality != "auto" {
-                command.args(&["--audio-quality", &options.quality]);
-            } else {
-                command.args(&["--audio-quality", "0"]);
-            }
-        }
-        _ => {
-            command.args(&["-f", "bestaudio", "-x", "--audio-format", &options.format, "--audio-quality", "0"]);
-        }
+        // 停止操作でCtrl+Breakを送れるよう、yt-dlpを別のプロセスグループで起動する
+        command.creation_flags(0x08000000 | 0x00000200); // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
     }
 
-    // HDR設定
-    if options.hdr_mode {
-        command.args(&["--format-sort", "hdr,res,codec,ext,size"]);
-    }
-
-    // 追加オプション
-    if options.chapter_embed {
-        command.args(&["--embed-chapters", "--add-chapters"]);
-    }
+    command.args(extra_args);
 
-    if options.concurrent_connections != "" && options.concurrent_connections != "0" {
-        command.args(&["-N", &options.concurrent_connections]);
-    }
-
-    // 出力パス設定
-    if options.playlist_mode {
-        command.args(&["-o", &format!("{}/%(playlist_title)s/%(playlist_index)03d_%(title).100s.%(ext)s", options.output_directory)]);
+    // 後処理のために保存先のファイルパスを書き出させる
+    let filepath_log = env::temp_dir().join(format!(
+        "necd-files-{}-{}.txt",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default()
+    ));
+    if cover::uses_pipeline(options) {
+        // カバー画像の処理で高解像度のサムネイルを選べるよう一覧も書き出す
+        command.args(["--print-to-file", "after_move:%(filepath)s\t%(thumbnails)j"]);
     } else {
-        command.args(&["-o", &format!("{}/%(title).100s.%(ext)s", options.output_directory)]);
+        command.args(["--print-to-file", "after_move:filepath"]);
     }
+    command.arg(&filepath_log);
+    // URLは最後に渡すため、他の引数を追加した後に組み立てる
+    command.args(args::build_download_args(options));
 
-    // サムネイル設定
-    if options.thumbnail_embed {
-        command.args(&["--embed-thumbnail", "--convert-thumbnails", "jpg"]);
-        if options.thumbnail_crop {
-            command.args(&["--ppa", "ThumbnailsConvertor:-qmin 1 -q:v 1 -vf crop=\"'if(gt(ih,iw),iw,ih)':'if(gt(iw,ih),ih,iw)'\""]);
-        }
-    }
+    let command_line = std::iter::once(yt_dlp_path.to_string())
+        .chain(command.as_std().get_args().map(|arg| arg.to_string_lossy().to_string()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    log.line("app", &format!("$ {}", command_line));
 
     // コマンド実行（リアルタイム出力）
     let mut child = command
@@ -189,16 +271,62 @@ async fn execute_download(window: tauri::Window, options: DownloadOptions) -> Re
         .env("PYTHONIOENCODING", "utf-8")
         .env("PYTHONLEGACYWINDOWSSTDIO", "utf-8")
         .spawn()
-        .map_err(|e| format!("コマンド実行エラー: {}", e))?;
+        .map_err(|e| {
+            let message = i18n::Message::new("command.spawn_failed").arg("error", e).text();
+            log.line("app", &message);
+            message
+        })?;
+
+    let stdout = child.stdout.take().ok_or_else(|| i18n::Message::new("command.pipe_failed").arg("stream", "stdout"))?;
+    let stderr = child.stderr.take().ok_or_else(|| i18n::Message::new("command.pipe_failed").arg("stream", "stderr"))?;
 
-    let stdout = child.stdout.take().ok_or("stdout取得エラー")?;
-    let stderr = child.stderr.take().ok_or("stderr取得エラー")?;
+    if let Some(pid) = child.id() {
+        jobs::register(job_id, pid);
+    }
+
+    // 録画時間の上限に達したら停止する（計測は録画の開始から）
+    let recording_started = std::sync::Arc::new(tokio::sync::Notify::new());
+    let timer_handle = live::max_duration(options).map(|duration| {
+        let started = recording_started.clone();
+        let job_id = job_id.to_string();
+        let events_handle = events.clone();
+        tokio::spawn(async move {
+            started.notified().await;
+            tokio::time::sleep(duration).await;
+            events_handle.send("download-log", RealTimeLog::localized(
+                i18n::Message::new("recording.max_duration"),
+                false,
+                None,
+            ));
+            let _ = jobs::request_stop(&job_id);
+        })
+    });
 
     // リアルタイムログ送信用のタスク
-    let window_handle = window.clone();
+    let events_handle = events.clone();
+    let recording_job = job_id.to_string();
+    let started = recording_started.clone();
+    let stdout_log = log.clone();
     let stdout_handle = tokio::spawn(async move {
         let mut reader = BufReader::new(stdout).lines();
+        let mut is_recording = false;
         while let Ok(Some(line)) = reader.next_line().await {
+            stdout_log.line("stdout", &line);
+            // 中断した場合に途中ファイルを特定できるよう書き込み先を記録する
+            if let Some(destination) = line.strip_prefix("[download] Destination: ") {
+                resume::record_file(&recording_job, destination.trim());
+            }
+            // ライブ録画の進捗
+            if let Some(recording) = live::parse_progress(&recording_job, &line) {
+                if !is_recording {
+                    is_recording = true;
+                    started.notify_one();
+                }
+                events_handle.send("download-log", RealTimeLog::localized(recording.i18n.clone(), false, None));
+                events_handle.send("recording-progress", recording);
+                continue;
+            }
+
             // プログレス情報の解析
             let progress = if line.contains("[DOWNLOADING]:") {
                 if let Some(percent_str) = line.split("[DOWNLOADING]:").nth(1) {
@@ -218,15 +346,19 @@ async fn execute_download(window: tauri::Window, options: DownloadOptions) -> Re
                 message: line,
                 is_error: false,
                 progress,
+                i18n: None,
             };
-            let _ = window_handle.emit("download-log", log);
+            events_handle.send("download-log", log);
         }
     });
 
-    let window_handle2 = window.clone();
+    let events_handle2 = events.clone();
+    let stderr_log = log.clone();
     let stderr_handle = tokio::spawn(async move {
         let mut reader = BufReader::new(stderr).lines();
+        let mut download_errors: Vec<errors::DownloadError> = Vec::new();
         while let Ok(Some(line)) = reader.next_line().await {
+            stderr_log.line("stderr", &line);
             // stderrからのプログレス情報も解析
             let progress = if line.contains("[DOWNLOADING]:") {
                 if let Some(percent_str) = line.split("[DOWNLOADING]:").nth(1) {
@@ -242,13 +374,21 @@ async fn execute_download(window: tauri::Window, options: DownloadOptions) -> Re
                 None
             };
             
+            // ERROR:行は種類を判定してUIに通知する
+            if let Some(error) = errors::classify(&line) {
+                events_handle2.send("download-error", error.clone());
+                download_errors.push(error);
+            }
+
             let log = RealTimeLog {
                 message: line,
                 is_error: true,
                 progress,
+                i18n: None,
             };
-            let _ = window_handle2.emit("download-log", log);
+            events_handle2.send("download-log", log);
         }
+        download_errors
     });
 
     // 両方のハンドルを待つ
@@ -256,46 +396,580 @@ async fn execute_download(window: tauri::Window, options: DownloadOptions) -> Re
     
     // エラーハンドリング
     if let Err(e) = stdout_result {
-        eprintln!("stdout読み取りエラー: {}", e);
-    }
-    if let Err(e) = stderr_result {
-        eprintln!("stderr読み取りエラー: {}", e);
+        let message = i18n::Message::new("command.read_failed").arg("stream", "stdout").arg("error", e).text();
+        eprintln!("{}", message);
+        log.line("app", &message);
     }
+    let download_errors = match stderr_result {
+        Ok(download_errors) => download_errors,
+        Err(e) => {
+            let message = i18n::Message::new("command.read_failed").arg("stream", "stderr").arg("error", e).text();
+            eprintln!("{}", message);
+            log.line("app", &message);
+            Vec::new()
+        }
+    };
 
     // プロセス終了を待つ
-    let status = child.wait().await.map_err(|e| format!("プロセス待機エラー: {}", e))?;
+    let status = child.wait().await;
+    if let Some(timer) = timer_handle {
+        timer.abort();
+    }
+    let stopped = jobs::stop_requested(job_id);
+    jobs::unregister(job_id);
+    let status = status.map_err(|e| i18n::Message::new("command.wait_failed").arg("error", e))?;
+    let code = status.code().map(|code| code.to_string()).unwrap_or_else(|| "-".to_string());
+    log.line("app", &i18n::Message::new(if stopped { "joblog.exit_code_stopped" } else { "joblog.exit_code" }).arg("code", code).text());
+
+    let mut files: Vec<String> = Vec::new();
+    let mut thumbnails: Vec<Option<Value>> = Vec::new();
+    if let Ok(content) = fs::read_to_string(&filepath_log) {
+        for line in content.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (file, thumbs) = match line.split_once('\t') {
+                Some((file, json)) => (file, serde_json::from_str::<Value>(json).ok()),
+                None => (line, None),
+            };
+            if !files.iter().any(|existing| existing == file) {
+                files.push(file.to_string());
+                thumbnails.push(thumbs);
+            }
+        }
+    }
+    let _ = fs::remove_file(&filepath_log);
+
+    Ok(YtDlpRun {
+        success: status.success(),
+        exit_code: status.code(),
+        stopped,
+        files,
+        thumbnails,
+        errors: download_errors,
+    })
+}
+
+#[tauri::command]
+async fn execute_download(window: tauri::Window, mut options: DownloadOptions) -> Result<DownloadProgress, i18n::Message> {
+    // キューを通さずに実行するジョブも一覧と停止の対象にする
+    let job_id = options.job_id.clone().filter(|id| !id.is_empty()).unwrap_or_else(jobs::generate_id);
+    options.job_id = Some(job_id.clone());
+    queue::track(&job_id, &options, "app", "running");
+    let result = download(&queue::JobSink::new(window, &job_id), options).await;
+    queue::finish(&job_id, &result);
+    result
+}
+
+// キューに追加する（同時に実行する数はmaxConcurrentDownloadsまで）
+#[tauri::command]
+async fn enqueue_download(options: DownloadOptions) -> Result<queue::JobInfo, i18n::Message> {
+    Ok(queue::enqueue(options, "app"))
+}
+
+// 待機中・実行中・最近終了したジョブ
+#[tauri::command]
+async fn list_jobs() -> Result<Vec<queue::JobInfo>, i18n::Message> {
+    Ok(queue::list())
+}
+
+// 待機中のジョブを取り消すか、実行中のジョブを停止する
+#[tauri::command]
+async fn cancel_job(job_id: String) -> Result<queue::JobInfo, i18n::Message> {
+    queue::cancel(&job_id)
+}
+
+// ローカルHTTP APIの状態（トークンと待ち受けURL）
+#[tauri::command]
+async fn get_api_server_status() -> Result<server::ApiServerStatus, i18n::Message> {
+    Ok(server::status())
+}
+
+// ローカルHTTP APIを有効・無効にする（portを省略すると現在のポート）
+#[tauri::command]
+async fn configure_api_server(enabled: bool, port: Option<u16>) -> Result<server::ApiServerStatus, i18n::Message> {
+    server::configure(enabled, port).await
+}
+
+#[tauri::command]
+async fn regenerate_api_token() -> Result<server::ApiServerStatus, i18n::Message> {
+    server::regenerate_token().await
+}
+
+// ダウンロードを実行する（GUIとCLIで共通。進捗はeventsに送る）
+pub(crate) async fn download<E: EventSink>(events: &E, mut options: DownloadOptions) -> Result<DownloadProgress, i18n::Message> {
+    // トラッキング用のパラメータを取り除き、動画とプレイリストの両方を指すURLはplaylist_modeで選ぶ
+    options.url = urls::resolve(&options.url, options.playlist_mode);
+
+    // Windows環境での文字エンコーディング設定
+    #[cfg(target_os = "windows")]
+    {
+        env::set_var("PYTHONIOENCODING", "utf-8");
+        env::set_var("PYTHONLEGACYWINDOWSSTDIO", "utf-8");
+        env::set_var("PYTHONUTF8", "1");
+    }
+    
+    // yt-dlpのパスを動的に取得
+    let yt_dlp_path = get_yt_dlp_path().await?;
+
+    // 保存先と空き容量を確認してから開始する
+    let probed = preflight::probe(&yt_dlp_path, &options).await;
+    match preflight::check(&options, probed.as_deref()) {
+        Ok(report) => {
+            if report.created {
+                events.send("download-log", RealTimeLog::localized(
+                    i18n::Message::new("download.directory_created").arg("path", &report.directory),
+                    false,
+                    None,
+                ));
+            }
+            if let (Some(estimated), Some(available)) = (report.estimated_bytes, report.available_bytes) {
+                events.send("download-log", RealTimeLog::localized(
+                    i18n::Message::new("download.size_estimate")
+                        .arg("estimated", preflight::format_size(estimated))
+                        .arg("available", preflight::format_size(available)),
+                    false,
+                    None,
+                ));
+            }
+        }
+        Err(e) => {
+            // キーは保存先の問題そのもの（表示用の文字列には❌を付ける）
+            let localized = e.localized();
+            let message = format!("❌ {}", localized.text());
+            events.send("download-log", RealTimeLog {
+                message: message.clone(),
+                is_error: true,
+                progress: Some(0.0),
+                i18n: Some(localized.clone()),
+            });
+            return Ok(DownloadProgress {
+                message,
+                progress: Some(0.0),
+                is_error: true,
+                files: Vec::new(),
+                loudness: Vec::new(),
+                stopped: false,
+                preflight_error: Some(e),
+                collision: None,
+                errors: Vec::new(),
+                attempts: Vec::new(),
+                i18n: Some(localized),
+            });
+        }
+    }
 
-    if status.success() {
-        let final_log = RealTimeLog {
-            message: "✅ ダウンロードが完了しました".to_string(),
+    // 同名ファイルの扱いを決める
+    let resolution = collision::resolve(&options, probed.as_deref());
+    let collision_report = resolution.report.clone();
+    if !resolution.proceed {
+        let message = i18n::Message::new(if collision_report.action == "ask" {
+            "collision.ask"
+        } else {
+            "collision.skipped"
+        });
+        events.send("download-log", RealTimeLog::localized(message.clone(), false, None));
+        return Ok(DownloadProgress {
+            message: message.text(),
+            progress: Some(0.0),
             is_error: false,
-            progress: Some(100.0),
+            files: collision_report.existing.clone(),
+            loudness: Vec::new(),
+            stopped: false,
+            preflight_error: None,
+            collision: Some(collision_report),
+            errors: Vec::new(),
+            attempts: Vec::new(),
+            i18n: Some(message),
+        });
+    }
+    match collision_report.action.as_str() {
+        "overwritten" => {
+            events.send("download-log", RealTimeLog::localized(
+                i18n::Message::new("collision.overwriting").arg("count", collision_report.existing.len()),
+                false,
+                None,
+            ));
+        }
+        "renamed" => {
+            events.send("download-log", RealTimeLog::localized(
+                i18n::Message::new("collision.renaming").arg("suffix", collision_report.suffix.as_deref().unwrap_or_default().trim()),
+                false,
+                None,
+            ));
+        }
+        "partially_skipped" => {
+            events.send("download-log", RealTimeLog::localized(
+                i18n::Message::new("collision.partially_skipped").arg("count", collision_report.existing.len()),
+                false,
+                None,
+            ));
+        }
+        _ => {}
+    }
+
+    let job_id = options.job_id.clone().filter(|id| !id.is_empty()).unwrap_or_else(jobs::generate_id);
+    let cover_pipeline = cover::uses_pipeline(&options);
+
+    // yt-dlpの出力をジョブごとのログファイルにも残す
+    let log = joblog::JobLog::open(&job_id);
+    log.line("app", &i18n::Message::new("joblog.started")
+        .arg("url", &options.url)
+        .arg("format", &options.format)
+        .arg("quality", &options.quality)
+        .arg("directory", &options.output_directory)
+        .text());
+
+    // アプリが途中で終了しても再開できるよう状態を保存しておく
+    if let Err(e) = resume::save(&job_id, &options, probed.as_deref()) {
+        events.send("download-log", RealTimeLog::localized(
+            i18n::Message::new("common.warning").arg("detail", e),
+            true,
+            None,
+        ));
+    }
+
+    // 一時的なエラーで失敗した場合は待ち時間を延ばしながら再実行する
+    let policy = retry::RetryPolicy::from_options(&options);
+    let mut attempts: Vec<retry::AttemptRecord> = Vec::new();
+    let run = loop {
+        let attempt = attempts.len() as u32 + 1;
+        let started_at = retry::now();
+        let extra_args = if attempt == 1 { resolution.args.clone() } else { collision::retry_args(&resolution.args) };
+        let run = match run_yt_dlp(events, &yt_dlp_path, &options, &extra_args, &job_id, &log).await {
+            Ok(run) => run,
+            Err(e) => {
+                log.line("app", &i18n::Message::new("joblog.failed").arg("error", &e).text());
+                resume::remove(&job_id);
+                return Err(e);
+            }
+        };
+        let retry_in = (!run.success && !run.stopped && attempt < policy.max_attempts && retry::is_transient(&run.errors))
+            .then(|| policy.delay(attempt));
+        let record = retry::AttemptRecord {
+            job_id: job_id.clone(),
+            attempt,
+            max_attempts: policy.max_attempts,
+            started_at,
+            success: run.success,
+            exit_code: run.exit_code,
+            error: if run.success { None } else { run.errors.last().cloned() },
+            retry_in_seconds: retry_in.map(|delay| delay.as_secs()),
+        };
+        events.send("download-attempt", record.clone());
+        attempts.push(record);
+
+        match retry_in {
+            Some(delay) => {
+                let message = i18n::Message::new("download.retry_scheduled")
+                    .arg("seconds", delay.as_secs())
+                    .arg("attempt", attempt + 1)
+                    .arg("max", policy.max_attempts);
+                log.line("app", &message.text());
+                events.send("download-log", RealTimeLog::localized(message, false, None));
+                tokio::time::sleep(delay).await;
+            }
+            None => break run,
+        }
+    };
+    let YtDlpRun { success, stopped, mut files, thumbnails, errors: download_errors, .. } = run;
+    resume::remove(&job_id);
+    if success || (stopped && !files.is_empty()) {
+        log.line("app", &i18n::Message::new("joblog.completed").arg("count", files.len()).text());
+        for file in &files {
+            log.line("app", &i18n::Message::new("joblog.saved").arg("path", file).text());
+        }
+    } else if stopped {
+        log.line("app", &i18n::Message::new("joblog.stopped").text());
+    } else {
+        let error = match download_errors.last() {
+            Some(error) => error.message.clone(),
+            None => i18n::Message::new("error.unknown").text(),
         };
-        let _ = window.emit("download-log", final_log);
+        log.line("app", &i18n::Message::new("joblog.failed").arg("error", error).text());
+    }
+
+    // 停止操作で終了した録画は、確定できたファイルがあれば成功として扱う
+    if success || (stopped && !files.is_empty()) {
+        let final_log = RealTimeLog::localized(
+            i18n::Message::new(if stopped { "recording.stopped" } else { "download.completed" }),
+            false,
+            Some(100.0),
+        );
+        events.send("download-log", final_log);
+
+        // ラウドネス処理（音声形式のみ）
+        let mut loudness_results = Vec::new();
+        let loudness_mode = options.loudness_mode.as_str();
+        if !loudness_mode.is_empty() && loudness_mode != "none" && !args::VIDEO_FORMATS.contains(&options.format.as_str()) {
+            for file in &files {
+                match loudness::apply(std::path::Path::new(file), loudness_mode, options.loudness_target).await {
+                    Ok(result) => {
+                        events.send("download-log", RealTimeLog::localized(
+                            i18n::Message::new("download.loudness_applied")
+                                .arg("lufs", format!("{:.1}", result.input_lufs))
+                                .arg("gain", format!("{:+.1}", result.gain_db)),
+                            false,
+                            None,
+                        ));
+                        loudness_results.push(result);
+                    }
+                    Err(e) => {
+                        events.send("download-log", RealTimeLog::localized(
+                            i18n::Message::new("common.warning").arg("detail", e),
+                            true,
+                            None,
+                        ));
+                    }
+                }
+            }
+        }
+
+        // カバー画像の作成・埋め込み・保存
+        if cover_pipeline {
+            let settings = cover::CoverSettings::from_options(&options);
+            let embed = options.thumbnail_embed && !args::VIDEO_FORMATS.contains(&options.format.as_str());
+            let mut saved_dirs: Vec<PathBuf> = Vec::new();
+            for (file, thumbs) in files.iter().zip(&thumbnails) {
+                let path = std::path::Path::new(file);
+                let dir = path.parent().map(|dir| dir.to_path_buf()).unwrap_or_default();
+                // cover.*はフォルダごとに最初のファイルから作成する
+                let save = cover::saves_cover_file(&options) && !saved_dirs.contains(&dir);
+                match cover::process(path, thumbs.as_ref(), &settings, embed, save).await {
+                    Ok(Some(cover_file)) => {
+                        saved_dirs.push(dir);
+                        events.send("download-log", RealTimeLog::localized(
+                            i18n::Message::new("download.cover_saved").arg("path", cover_file.display()),
+                            false,
+                            None,
+                        ));
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        events.send("download-log", RealTimeLog::localized(
+                            i18n::Message::new("common.warning").arg("detail", e),
+                            true,
+                            None,
+                        ));
+                    }
+                }
+            }
+        }
+
+        // 再エンコード
+        if let Some(preset) = options.reencode_preset.as_deref().filter(|preset| !preset.is_empty()) {
+            let mut processed = Vec::new();
+            for file in &files {
+                match postprocess::run_preset(events, std::path::Path::new(file), preset, options.delete_original).await {
+                    Ok(output) => processed.push(output.display().to_string()),
+                    Err(e) => {
+                        events.send("download-log", RealTimeLog::localized(
+                            i18n::Message::new("common.error").arg("detail", e),
+                            true,
+                            None,
+                        ));
+                        let message = i18n::Message::new("download.reencode_failed");
+                        return Ok(DownloadProgress {
+                            message: message.text(),
+                            progress: Some(0.0),
+                            is_error: true,
+                            files,
+                            loudness: loudness_results,
+                            stopped,
+                            preflight_error: None,
+                            collision: Some(collision_report.clone()),
+                            errors: Vec::new(),
+                            attempts,
+                            i18n: Some(message),
+                        });
+                    }
+                }
+            }
+            files = processed;
+        }
         
+        let message = i18n::Message::new("download.finished");
         Ok(DownloadProgress {
-            message: "✅ 正常に完了しました".to_string(),
+            message: message.text(),
             progress: Some(1.0),
             is_error: false,
+            files,
+            loudness: loudness_results,
+            stopped,
+            preflight_error: None,
+            collision: Some(collision_report),
+            errors: Vec::new(),
+            attempts,
+            i18n: Some(message),
+        })
+    } else if stopped {
+        // 停止操作で終了した場合は失敗として扱わない
+        let message = i18n::Message::new("download.stopped");
+        events.send("download-log", RealTimeLog::localized(message.clone(), false, None));
+        Ok(DownloadProgress {
+            message: message.text(),
+            progress: None,
+            is_error: false,
+            files,
+            loudness: Vec::new(),
+            stopped,
+            preflight_error: None,
+            collision: Some(collision_report),
+            errors: Vec::new(),
+            attempts,
+            i18n: Some(message),
         })
     } else {
-        let final_log = RealTimeLog {
-            message: "❌ ダウンロードに失敗しました".to_string(),
-            is_error: true,
-            progress: Some(0.0),
-        };
-        let _ = window.emit("download-log", final_log);
-        
+        let final_log = RealTimeLog::localized(i18n::Message::new("download.failed"), true, Some(0.0));
+        events.send("download-log", final_log);
+
+        // 最後のエラーが失敗の直接の原因であることが多い
+        let localized = download_errors
+            .last()
+            .map(|error| error.i18n.clone())
+            .unwrap_or_else(|| i18n::Message::new(errors::ErrorKind::Unknown.key()));
+        let message = format!("❌ {}", localized.text());
+
         Ok(DownloadProgress {
-            message: "❌ エラーが発生しました".to_string(),
+            message,
             progress: Some(0.0),
             is_error: true,
+            files,
+            loudness: Vec::new(),
+            stopped,
+            preflight_error: None,
+            collision: Some(collision_report),
+            errors: download_errors,
+            attempts,
+            i18n: Some(localized),
         })
     }
 }
 
+// バックエンドのメッセージに使う言語（"ja"または"en"）
 #[tauri::command]
-async fn get_default_download_directory() -> Result<String, String> {
+async fn get_locale() -> Result<i18n::Locale, i18n::Message> {
+    Ok(i18n::current())
+}
+
+// メッセージキーとテンプレートの一覧（localeを省略すると現在の言語）
+#[tauri::command]
+async fn get_message_catalog(locale: Option<String>) -> Result<i18n::Catalog, i18n::Message> {
+    let locale = locale.as_deref().and_then(i18n::Locale::parse).unwrap_or_else(i18n::current);
+    Ok(i18n::catalog(locale))
+}
+
+// ログが残っているジョブの一覧（新しい順）
+#[tauri::command]
+async fn list_job_logs() -> Result<Vec<joblog::JobLogInfo>, i18n::Message> {
+    Ok(joblog::list())
+}
+
+// ジョブの実行ログを取得する
+#[tauri::command]
+async fn get_job_log(job_id: String) -> Result<String, i18n::Message> {
+    joblog::read(&job_id)
+}
+
+// 不具合報告用にログをまとめて書き出す（job_idsを省略すると全てのログ）
+#[tauri::command]
+async fn export_logs(destination: String, job_ids: Option<Vec<String>>) -> Result<String, i18n::Message> {
+    joblog::export(&destination, &job_ids.unwrap_or_default())
+}
+
+// 前回の終了時に実行中だったジョブ
+#[tauri::command]
+async fn list_interrupted_jobs() -> Result<Vec<resume::InterruptedJob>, i18n::Message> {
+    Ok(resume::list_interrupted())
+}
+
+// 中断されたジョブを途中ファイルから再開する
+#[tauri::command]
+async fn resume_job(window: tauri::Window, job_id: String) -> Result<DownloadProgress, i18n::Message> {
+    if jobs::is_running(&job_id) {
+        return Err(i18n::Message::new("job.already_running").arg("job", job_id));
+    }
+    let mut options = resume::load(&job_id)?.options;
+    options.job_id = Some(job_id);
+    options.resume = true;
+    execute_download(window, options).await
+}
+
+// 中断されたジョブを破棄し、途中ファイルを削除する
+#[tauri::command]
+async fn discard_job(job_id: String) -> Result<Vec<String>, i18n::Message> {
+    resume::discard(&job_id)
+}
+
+// ライブ録画を停止する（yt-dlpがファイルを確定してから終了する）
+#[tauri::command]
+async fn stop_recording(job_id: String) -> Result<(), i18n::Message> {
+    jobs::request_stop(&job_id)
+}
+
+// ダウンロードせずに保存先と空き容量を確認する
+#[tauri::command]
+async fn preflight_download(options: DownloadOptions) -> Result<preflight::PreflightReport, preflight::PreflightError> {
+    let yt_dlp_path = tools::yt_dlp();
+    let probed = preflight::probe(&yt_dlp_path, &options).await;
+    preflight::check(&options, probed.as_deref())
+}
+
+#[tauri::command]
+async fn list_reencode_presets() -> Result<Vec<postprocess::PresetInfo>, i18n::Message> {
+    Ok(postprocess::list_presets())
+}
+
+// 実行されるyt-dlpコマンドをダウンロードせずに確認する
+#[tauri::command]
+async fn preview_download_command(options: DownloadOptions) -> Result<CommandPreview, i18n::Message> {
+    let yt_dlp_path = get_yt_dlp_path().await?;
+    let args = args::build_download_args(&options);
+    let command = std::iter::once(yt_dlp_path.as_str())
+        .chain(args.iter().map(|arg| arg.as_str()))
+        .map(args::quote_arg)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(CommandPreview {
+        command,
+        args,
+        constraints: args::describe_constraints(&options),
+    })
+}
+
+#[tauri::command]
+async fn get_default_music_rules() -> Result<Vec<music::MetadataRule>, i18n::Message> {
+    Ok(music::default_rules())
+}
+
+// ミュージックモードのルールを適用した結果をダウンロードせずに確認する
+#[tauri::command]
+async fn preview_music_metadata(url: String, rules: Vec<music::MetadataRule>) -> Result<Vec<music::MusicMetadataPreview>, i18n::Message> {
+    let yt_dlp_path = get_yt_dlp_path().await?;
+    music::preview(&yt_dlp_path, &url, &rules).await
+}
+
+#[tauri::command]
+async fn read_tags(path: String) -> Result<tags::MediaTags, i18n::Message> {
+    tags::read_tags(std::path::Path::new(&path)).await
+}
+
+#[tauri::command]
+async fn write_tags(path: String, edit: tags::TagEdit) -> Result<(), i18n::Message> {
+    tags::write_tags(std::path::Path::new(&path), &edit).await
+}
+
+// プレイリストなど複数ファイルのタグをまとめて編集する
+#[tauri::command]
+async fn write_tags_batch(files: Vec<String>, edit: tags::TagEdit, auto_track_number: bool) -> Result<Vec<tags::TagWriteResult>, i18n::Message> {
+    Ok(tags::write_tags_batch(&files, &edit, auto_track_number).await)
+}
+
+#[tauri::command]
+async fn list_media_files(directory: String) -> Result<Vec<String>, i18n::Message> {
+    tags::list_media_files(std::path::Path::new(&directory))
+}
+
+#[tauri::command]
+async fn get_default_download_directory() -> Result<String, i18n::Message> {
     // デフォルトのダウンロードディレクトリを取得
     #[cfg(target_os = "windows")]
     {
@@ -329,194 +1003,172 @@ async fn get_default_download_directory() -> Result<String, String> {
 }
 
 #[tauri::command]
-async fn get_title_from_url(url: String) -> Result<String, String> {
-    use tokio::process::Command;
-    let mut cmd = Command::new("yt-dlp");
+async fn get_title_from_url(url: String) -> Result<String, i18n::Message> {
+    let yt_dlp_path = get_yt_dlp_path().await?;
+    let mut cmd = tools::command(&yt_dlp_path);
     cmd.arg("-J")
         .arg("--flat-playlist")
+        .arg("--")
         .arg(&url);
-    #[cfg(target_os = "windows")]
-    {
-        use std::os::windows::process::CommandExt;
-        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-    }
     let output = cmd.output()
         .await
-        .map_err(|e| format!("yt-dlp実行エラー: {}", e))?;
+        .map_err(|e| i18n::Message::new("command.run_failed").arg("program", "yt-dlp").arg("error", e))?;
     if !output.status.success() {
-        return Err(format!("yt-dlpエラー: {}", String::from_utf8_lossy(&output.stderr)));
+        return Err(i18n::Message::new("command.failed").arg("program", "yt-dlp").arg("error", String::from_utf8_lossy(&output.stderr)));
     }
     let json: Value = serde_json::from_slice(&output.stdout)
-        .map_err(|e| format!("JSONパースエラー: {}", e))?;
+        .map_err(|e| i18n::Message::new("common.json_parse_failed").arg("error", e))?;
     if let Some(title) = json.get("title").and_then(|v| v.as_str()) {
         Ok(title.to_string())
     } else {
-        Err("タイトルが取得できませんでした".to_string())
+        Err(i18n::Message::new("title.not_found"))
     }
 }
 
-async fn get_yt_dlp_path() -> Result<String, String> {
-    // macOSの場合、pyenvのパスを優先的に確認
-    #[cfg(target_os = "macos")]
-    {
-        // ユーザーホームディレクトリを動的に取得
-        let home_dir = dirs::home_dir().ok_or("ホームディレクトリを取得できませんでした")?;
-        
-        // pyenvのパスを複数の方法で確認
-        let mut pyenv_paths = vec![];
-        
-        // 1. デフォルトのpyenvパス
-        let default_pyenv = home_dir.join(".pyenv").join("shims").join("yt-dlp");
-        pyenv_paths.push(default_pyenv);
-        
-        // 2. PYENV_ROOT環境変数が設定されている場合
-        if let Ok(pyenv_root) = env::var("PYENV_ROOT") {
-            let custom_pyenv = PathBuf::from(pyenv_root).join("shims").join("yt-dlp");
-            pyenv_paths.push(custom_pyenv);
-        }
-        
-        // 3. 一般的なシステムパス
-        pyenv_paths.push(PathBuf::from("/opt/homebrew/bin/yt-dlp"));
-        pyenv_paths.push(PathBuf::from("/usr/local/bin/yt-dlp"));
-        
-        for path in pyenv_paths {
-            if path.exists() {
-                return Ok(path.to_string_lossy().to_string());
-            }
-        }
-        
-        // whichコマンドで検索
-        match Command::new("which").arg("yt-dlp").output() {
-            Ok(output) => {
-                if output.status.success() {
-                    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                    if !path.is_empty() {
-                        return Ok(path);
-                    }
-                }
-            }
-            Err(_) => {}
-        }
-    }
-    
-    // デフォルトは"yt-dlp"
-    Ok("yt-dlp".to_string())
+async fn get_yt_dlp_path() -> Result<String, i18n::Message> {
+    // 設定での指定 → アプリが管理しているもの → PATHと一般的なインストール先の順
+    Ok(tools::yt_dlp())
+}
+
+// 検出したyt-dlp・ffmpeg・ffprobeのパス
+#[tauri::command]
+async fn get_tool_paths() -> Result<tools::ToolPaths, i18n::Message> {
+    Ok(tools::all())
 }
 
 #[tauri::command]
-async fn check_yt_dlp_installed() -> Result<bool, String> {
+async fn check_yt_dlp_installed() -> Result<bool, i18n::Message> {
     let yt_dlp_path = get_yt_dlp_path().await?;
-    match Command::new(&yt_dlp_path).arg("--version").output() {
-        Ok(_) => Ok(true),
+    match tools::command(&yt_dlp_path).arg("--version").output().await {
+        Ok(output) => Ok(output.status.success()),
         Err(_) => Ok(false),
     }
 }
 
+// 実行環境を診断する（引数を省略した項目は保存済みの設定を使う）
 #[tauri::command]
-async fn install_yt_dlp() -> Result<String, String> {
-    #[cfg(target_os = "windows")]
-    {
-        // Windows: pipを使用してインストール
-        match Command::new("pip").args(&["install", "--upgrade", "yt-dlp"]).output() {
-            Ok(output) => {
-                if output.status.success() {
-                    Ok("yt-dlpのインストールが完了しました".to_string())
-                } else {
-                    Err(format!("yt-dlpのインストールに失敗しました: {}", String::from_utf8_lossy(&output.stderr)))
-                }
-            }
-            Err(e) => Err(format!("pipコマンドの実行に失敗しました: {}", e)),
-        }
-    }
-    
-    #[cfg(not(target_os = "windows"))]
-    {
-        // Unix系: curlを使用してインストール
-        let install_script = r#"
-            if command -v curl >/dev/null 2>&1; then
-                curl -L https://github.com/yt-dlp/yt-dlp/releases/latest/download/yt-dlp -o /tmp/yt-dlp
-                chmod +x /tmp/yt-dlp
-                sudo mv /tmp/yt-dlp /usr/local/bin/yt-dlp
-                echo "yt-dlpのインストールが完了しました"
-            else
-                echo "curlがインストールされていません"
-                exit 1
-            fi
-        "#;
-        
-        match Command::new("sh").arg("-c").arg(install_script).output() {
-            Ok(output) => {
-                if output.status.success() {
-                    Ok("yt-dlpのインストールが完了しました".to_string())
-                } else {
-                    Err(format!("yt-dlpのインストールに失敗しました: {}", String::from_utf8_lossy(&output.stderr)))
-                }
-            }
-            Err(e) => Err(format!("インストールスクリプトの実行に失敗しました: {}", e)),
+async fn run_diagnostics(
+    output_directory: Option<String>,
+    cookie_file_path: Option<String>,
+    proxy: Option<String>,
+) -> Result<Vec<diagnostics::DiagnosticItem>, i18n::Message> {
+    let settings = load_settings().await.unwrap_or(Value::Null);
+    let saved = |key: &str| settings.get(key).and_then(|v| v.as_str()).map(str::to_string);
+
+    let output_directory = output_directory.or_else(|| saved("outputDirectory")).unwrap_or_default();
+    let cookie_file_path = cookie_file_path.or_else(|| {
+        if saved("cookieSource").as_deref() == Some("file") {
+            saved("cookieFilePath")
+        } else {
+            None
         }
-    }
+    });
+    Ok(diagnostics::run_all(&output_directory, cookie_file_path.as_deref(), proxy).await)
 }
 
 #[tauri::command]
-async fn read_clipboard() -> Result<String, String> {
-    // Windowsの場合
-    #[cfg(target_os = "windows")]
-    {
-        use std::os::windows::process::CommandExt;
-        use std::process::Command;
-        let mut cmd = Command::new("powershell");
-        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-        match cmd
-            .args(&["-command", "Get-Clipboard"])
-            .output()
-        {
-            Ok(output) => {
-                let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                Ok(text)
-            }
-            Err(_) => Err("クリップボードの読み込みに失敗しました".to_string()),
-        }
-    }
-    
-    // macOSの場合
-    #[cfg(target_os = "macos")]
-    {
-        use std::process::Command;
-        match Command::new("pbpaste").output() {
-            Ok(output) => {
-                let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                Ok(text)
-            }
-            Err(_) => Err("クリップボードの読み込みに失敗しました".to_string()),
-        }
-    }
-    
-    // Linuxの場合
-    #[cfg(target_os = "linux")]
-    {
-        use std::process::Command;
-        // xclipを試す
-        match Command::new("xclip").args(&["-o", "-selection", "clipboard"]).output() {
-            Ok(output) => {
-                let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                Ok(text)
-            }
-            Err(_) => {
-                // xclipが失敗したらxselを試す
-                match Command::new("xsel").args(&["--output", "--clipboard"]).output() {
-                    Ok(output) => {
-                        let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                        Ok(text)
-                    }
-                    Err(_) => Err("クリップボードの読み込みに失敗しました".to_string()),
-                }
-            }
-        }
-    }
+async fn install_yt_dlp(version: Option<String>) -> Result<String, i18n::Message> {
+    let installed = ytdlp::install(version).await?;
+    Ok(i18n::Message::new("ytdlp.installed").arg("version", installed).text())
 }
 
 #[tauri::command]
-async fn select_directory(default_path: Option<String>) -> Result<Option<String>, String> {
+async fn get_managed_yt_dlp() -> Result<ytdlp::ManagedInfo, i18n::Message> {
+    Ok(ytdlp::info())
+}
+
+#[tauri::command]
+async fn activate_yt_dlp_version(version: String) -> Result<(), i18n::Message> {
+    ytdlp::activate(&version)
+}
+
+#[tauri::command]
+async fn rollback_yt_dlp() -> Result<String, i18n::Message> {
+    ytdlp::rollback()
+}
+
+#[tauri::command]
+async fn set_yt_dlp_pinned_version(version: Option<String>) -> Result<(), i18n::Message> {
+    ytdlp::set_pin(version)
+}
+
+#[tauri::command]
+async fn set_yt_dlp_release_url(url: Option<String>) -> Result<(), i18n::Message> {
+    ytdlp::set_release_url(url)
+}
+
+// 文章や.txt/.csvファイルからURLを取り出し、まとめてキューに追加する
+#[tauri::command]
+async fn import_urls(request: batch::BatchImportRequest) -> Result<batch::BatchImportResult, i18n::Message> {
+    batch::import(request).await
+}
+
+// 保存したプリセットと既定のプリセット
+#[tauri::command]
+async fn list_presets() -> Result<presets::PresetStore, i18n::Message> {
+    Ok(presets::load())
+}
+
+// URLを除く現在のオプションを名前を付けて保存する
+#[tauri::command]
+async fn create_preset(name: String, options: DownloadOptions) -> Result<presets::Preset, i18n::Message> {
+    presets::create(&name, &options)
+}
+
+#[tauri::command]
+async fn update_preset(name: String, options: DownloadOptions) -> Result<presets::Preset, i18n::Message> {
+    presets::update(&name, &options)
+}
+
+#[tauri::command]
+async fn rename_preset(name: String, new_name: String) -> Result<presets::Preset, i18n::Message> {
+    presets::rename(&name, &new_name)
+}
+
+#[tauri::command]
+async fn delete_preset(name: String) -> Result<(), i18n::Message> {
+    presets::delete(&name)
+}
+
+#[tauri::command]
+async fn duplicate_preset(name: String, new_name: Option<String>) -> Result<presets::Preset, i18n::Message> {
+    presets::duplicate(&name, new_name.as_deref())
+}
+
+// nameがnullの場合は既定のプリセットを解除する
+#[tauri::command]
+async fn set_default_preset(name: Option<String>) -> Result<presets::PresetStore, i18n::Message> {
+    presets::set_default(name.as_deref())
+}
+
+// namesを省略するとすべて書き出す
+#[tauri::command]
+async fn export_presets(destination: String, names: Option<Vec<String>>) -> Result<String, i18n::Message> {
+    presets::export(&destination, names.as_deref())
+}
+
+// 読み込んだプリセットの名前を返す（overwriteがfalseの場合、同じ名前には番号を付ける）
+#[tauri::command]
+async fn import_presets(path: String, overwrite: bool) -> Result<Vec<String>, i18n::Message> {
+    presets::import(&path, overwrite)
+}
+
+// URLの種類を判定する（動画とプレイリストの両方を指す場合はambiguousになる）
+#[tauri::command]
+async fn classify_url(url: String) -> Result<urls::ClassifiedUrl, i18n::Message> {
+    urls::classify(&url)
+}
+
+#[tauri::command]
+async fn read_clipboard() -> Result<String, i18n::Message> {
+    tokio::task::spawn_blocking(clipboard::read_text)
+        .await
+        .map_err(|e| i18n::Message::new("clipboard.read_failed").arg("error", e))?
+        .map(|text| text.trim().to_string())
+}
+
+#[tauri::command]
+async fn select_directory(default_path: Option<String>) -> Result<Option<String>, i18n::Message> {
     let dialog = if let Some(path) = default_path {
         rfd::AsyncFileDialog::new().set_directory(PathBuf::from(path)).pick_folder().await
     } else {
@@ -526,7 +1178,7 @@ async fn select_directory(default_path: Option<String>) -> Result<Option<String>
 }
 
 #[tauri::command]
-async fn select_file(filters: Vec<FileFilter>) -> Result<Option<String>, String> {
+async fn select_file(filters: Vec<FileFilter>) -> Result<Option<String>, i18n::Message> {
     let mut dialog = rfd::AsyncFileDialog::new();
     for filter in filters {
         dialog = dialog.add_filter(&filter.name, &filter.extensions);
@@ -536,14 +1188,14 @@ async fn select_file(filters: Vec<FileFilter>) -> Result<Option<String>, String>
 }
 
 #[tauri::command]
-async fn open_directory(path: String) -> Result<(), String> {
+async fn open_directory(path: String) -> Result<(), i18n::Message> {
     #[cfg(target_os = "windows")]
     {
         use std::process::Command;
         Command::new("explorer")
             .arg(&path)
             .spawn()
-            .map_err(|e| format!("ディレクトリを開けませんでした: {}", e))?;
+            .map_err(|e| i18n::Message::new("common.open_directory_failed").arg("error", e))?;
     }
     
     #[cfg(target_os = "macos")]
@@ -552,7 +1204,7 @@ async fn open_directory(path: String) -> Result<(), String> {
         Command::new("open")
             .arg(&path)
             .spawn()
-            .map_err(|e| format!("ディレクトリを開けませんでした: {}", e))?;
+            .map_err(|e| i18n::Message::new("common.open_directory_failed").arg("error", e))?;
     }
     
     #[cfg(target_os = "linux")]
@@ -561,62 +1213,54 @@ async fn open_directory(path: String) -> Result<(), String> {
         Command::new("xdg-open")
             .arg(&path)
             .spawn()
-            .map_err(|e| format!("ディレクトリを開けませんでした: {}", e))?;
+            .map_err(|e| i18n::Message::new("common.open_directory_failed").arg("error", e))?;
     }
     
     Ok(())
 }
 
 #[tauri::command]
-async fn save_settings(settings: serde_json::Value) -> Result<(), String> {
+async fn save_settings(settings: serde_json::Value) -> Result<(), i18n::Message> {
     let app_dir = dirs::config_dir()
-        .ok_or("設定ディレクトリを取得できませんでした")?
+        .ok_or_else(|| i18n::Message::new("common.config_dir_unavailable"))?
         .join("necd-tauri");
     
     // ディレクトリが存在しない場合は作成
-    fs::create_dir_all(&app_dir).map_err(|e| format!("設定ディレクトリを作成できませんでした: {}", e))?;
+    fs::create_dir_all(&app_dir).map_err(|e| i18n::Message::new("common.config_dir_create_failed").arg("error", e))?;
     
     let settings_path = app_dir.join("settings.json");
     let settings_json = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("設定のシリアライズに失敗しました: {}", e))?;
+        .map_err(|e| i18n::Message::new("settings.serialize_failed").arg("error", e))?;
     
     fs::write(&settings_path, settings_json)
-        .map_err(|e| format!("設定の保存に失敗しました: {}", e))?;
+        .map_err(|e| i18n::Message::new("settings.save_failed").arg("error", e))?;
+
+    // 言語の設定が変わっている可能性がある
+    i18n::reset();
+    // クリップボードの監視の設定を反映する
+    clipboard::apply_settings();
     
     Ok(())
 }
 
 #[tauri::command]
-async fn load_settings() -> Result<serde_json::Value, String> {
+async fn load_settings() -> Result<serde_json::Value, i18n::Message> {
     let app_dir = dirs::config_dir()
-        .ok_or("設定ディレクトリを取得できませんでした")?
+        .ok_or_else(|| i18n::Message::new("common.config_dir_unavailable"))?
         .join("necd-tauri");
     
     let settings_path = app_dir.join("settings.json");
     
     if !settings_path.exists() {
         // デフォルト設定を返す
-        return Ok(serde_json::json!({
-            "outputDirectory": "",
-            "format": "mp4",
-            "quality": "auto",
-            "cookieSource": "none",
-            "cookieFilePath": "",
-            "concurrentConnections": "3",
-            "chapterEmbed": false,
-            "playlistMode": false,
-            "thumbnailEmbed": false,
-            "thumbnailCrop": false,
-            "compatibilityMode": false,
-            "hdrMode": false
-        }));
+        return Ok(settings::default_settings());
     }
     
     let settings_content = fs::read_to_string(&settings_path)
-        .map_err(|e| format!("設定ファイルの読み込みに失敗しました: {}", e))?;
+        .map_err(|e| i18n::Message::new("settings.read_failed").arg("error", e))?;
     
     let settings: serde_json::Value = serde_json::from_str(&settings_content)
-        .map_err(|e| format!("設定のデシリアライズに失敗しました: {}", e))?;
+        .map_err(|e| i18n::Message::new("settings.parse_failed").arg("error", e))?;
     
     Ok(settings)
 }
@@ -624,13 +1268,67 @@ async fn load_settings() -> Result<serde_json::Value, String> {
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        // 2つ目の起動はウィンドウを開かず、引数（necd://のリンクやURL）を起動中のアプリに渡して終了する
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            deeplink::handle_args(app, args.get(1..).unwrap_or_default());
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            queue::init(app.handle().clone());
+            clipboard::init(app.handle().clone());
+            deeplink::init(app.handle());
+            tauri::async_runtime::spawn(server::start_if_enabled());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_app_version,
             execute_download,
+            enqueue_download,
+            list_jobs,
+            cancel_job,
+            get_api_server_status,
+            configure_api_server,
+            regenerate_api_token,
+            stop_recording,
+            list_interrupted_jobs,
+            resume_job,
+            discard_job,
+            list_job_logs,
+            get_locale,
+            get_message_catalog,
+            get_job_log,
+            export_logs,
+            preflight_download,
+            preview_download_command,
+            list_reencode_presets,
+            get_default_music_rules,
+            preview_music_metadata,
+            read_tags,
+            write_tags,
+            write_tags_batch,
+            list_media_files,
             get_default_download_directory,
             check_yt_dlp_installed,
+            run_diagnostics,
+            get_tool_paths,
             install_yt_dlp,
+            get_managed_yt_dlp,
+            activate_yt_dlp_version,
+            rollback_yt_dlp,
+            set_yt_dlp_pinned_version,
+            set_yt_dlp_release_url,
+            list_presets,
+            create_preset,
+            update_preset,
+            rename_preset,
+            delete_preset,
+            duplicate_preset,
+            set_default_preset,
+            export_presets,
+            import_presets,
+            classify_url,
+            import_urls,
             read_clipboard,
             select_directory,
             select_file,
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use crate::i18n;
+use crate::preflight;
+use crate::DownloadOptions;
+
+// ライブ録画中の進捗（経過時間と書き出したサイズ）
+pub const PROGRESS_TEMPLATE: &str = "[RECORDING]:%(progress.elapsed|0)s:%(progress.downloaded_bytes|0)s";
+
+// 配信予定の枠を確認する間隔の既定値（秒）
+const DEFAULT_WAIT_INTERVAL: u64 = 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordingProgress {
+    pub job_id: String,
+    pub elapsed_seconds: f64,
+    pub downloaded_bytes: u64,
+    pub message: String,
+    pub i18n: i18n::Message,
+}
+
+// ライブ録画として扱うか
+pub fn is_recording(options: &DownloadOptions) -> bool {
+    matches!(options.live_mode.as_str(), "from_start" | "from_now")
+}
+
+// ライブ録画のyt-dlp引数
+pub fn live_args(options: &DownloadOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    match options.live_mode.as_str() {
+        "from_start" => args.push("--live-from-start".to_string()),
+        "from_now" => args.push("--no-live-from-start".to_string()),
+        _ => return args,
+    }
+    if options.wait_for_video {
+        let interval = options.wait_interval.filter(|interval| *interval > 0).unwrap_or(DEFAULT_WAIT_INTERVAL);
+        args.push("--wait-for-video".to_string());
+        args.push(interval.to_string());
+    }
+    args
+}
+
+// 録画時間の上限
+pub fn max_duration(options: &DownloadOptions) -> Option<std::time::Duration> {
+    options
+        .live_max_duration
+        .filter(|seconds| *seconds > 0)
+        .map(std::time::Duration::from_secs)
+}
+
+// PROGRESS_TEMPLATEの出力行を解析する
+pub fn parse_progress(job_id: &str, line: &str) -> Option<RecordingProgress> {
+    let rest = line.split("[RECORDING]:").nth(1)?;
+    let (elapsed, bytes) = rest.trim().split_once(':')?;
+    let elapsed_seconds = elapsed.parse::<f64>().unwrap_or(0.0);
+    let downloaded_bytes = bytes.parse::<f64>().map(|bytes| bytes as u64).unwrap_or(0);
+    let message = i18n::Message::new("recording.progress")
+        .arg("elapsed", format_elapsed(elapsed_seconds))
+        .arg("size", preflight::format_size(downloaded_bytes));
+    Some(RecordingProgress {
+        job_id: job_id.to_string(),
+        elapsed_seconds,
+        downloaded_bytes,
+        message: message.text(),
+        i18n: message,
+    })
+}
+
+fn format_elapsed(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", total / 3600, total / 60 % 60, total % 60)
+}
@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use tokio::process::Command as TokioCommand;
+
+use crate::i18n::Message;
+use crate::postprocess;
+use crate::tools;
+
+// ラウドネス処理の対象となる音声形式
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["mp3", "opus", "flac"];
+
+// ReplayGain 2.0の基準ラウドネス (LUFS)
+const REPLAYGAIN_REFERENCE: f64 = -18.0;
+// OpusのR128_*_GAINタグの基準ラウドネス (LUFS)
+const R128_REFERENCE: f64 = -23.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LoudnessResult {
+    pub file: String,
+    // "loudnorm" または "replaygain"
+    pub mode: String,
+    // 処理前の統合ラウドネス (LUFS)
+    pub input_lufs: f64,
+    // 処理前のトゥルーピーク (dBTP)
+    pub input_true_peak: f64,
+    // loudnormの目標値、またはReplayGainの基準値 (LUFS)
+    pub target_lufs: f64,
+    // 適用した（ReplayGainの場合はタグに書き込んだ）ゲイン (dB)
+    pub gain_db: f64,
+}
+
+// loudnormの1パス目で得られる測定値
+struct Measurement {
+    input_i: f64,
+    input_tp: f64,
+    input_lra: f64,
+    input_thresh: f64,
+    target_offset: f64,
+}
+
+fn ffmpeg_command() -> TokioCommand {
+    let mut cmd = tools::command(tools::ffmpeg());
+    cmd.args(["-hide_banner", "-nostdin"]);
+    cmd
+}
+
+// ffprobeで最初の音声ストリームのサンプルレートを取得
+async fn probe_sample_rate(input: &Path) -> Option<u32> {
+    let mut cmd = tools::command(tools::ffprobe());
+    cmd.args([
+        "-v", "error",
+        "-select_streams", "a:0",
+        "-show_entries", "stream=sample_rate",
+        "-of", "default=noprint_wrappers=1:nokey=1",
+    ])
+    .arg(input);
+    let output = cmd.output().await.ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse::<u32>().ok()
+}
+
+// loudnormの1パス目（解析のみ）
+async fn measure(input: &Path, target: f64) -> Result<Measurement, Message> {
+    let output = ffmpeg_command()
+        .arg("-i")
+        .arg(input)
+        .args([
+            "-map", "0:a:0",
+            "-af", &format!("loudnorm=I={}:TP=-1.5:LRA=11:print_format=json", target),
+            "-f", "null", "-",
+        ])
+        .output()
+        .await
+        .map_err(|e| Message::new("command.run_failed").arg("program", "ffmpeg").arg("error", e))?;
+    if !output.status.success() {
+        return Err(Message::new("loudness.measure_failed").arg("error", String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or_default()));
+    }
+
+    // 解析結果はstderrの末尾にJSONで出力される
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let json = stderr
+        .rfind('{')
+        .and_then(|start| stderr[start..].find('}').map(|end| &stderr[start..start + end + 1]))
+        .ok_or_else(|| Message::new("loudness.result_missing"))?;
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| Message::new("common.json_parse_failed").arg("error", e))?;
+    let field = |key: &str| -> Result<f64, Message> {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| Message::new("loudness.invalid_value").arg("key", key))
+    };
+
+    Ok(Measurement {
+        input_i: field("input_i")?,
+        input_tp: field("input_tp")?,
+        input_lra: field("input_lra")?,
+        input_thresh: field("input_thresh")?,
+        target_offset: field("target_offset")?,
+    })
+}
+
+// Oggはカバー画像の映像ストリームを書き出せないため音声のみを対象にする
+fn stream_map(extension: &str) -> &'static str {
+    if extension == "opus" {
+        "0:a"
+    } else {
+        "0"
+    }
+}
+
+// 一時ファイルに書き出してから元ファイルと置き換える
+async fn rewrite(input: &Path, extension: &str, args: Vec<String>) -> Result<(), Message> {
+    let stem = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .ok_or_else(|| Message::new("common.file_name_unavailable"))?;
+    let temp = input
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(format!("{}.necd-tmp.{}", stem, extension));
+
+    let output = ffmpeg_command()
+        .args(["-y", "-i"])
+        .arg(input)
+        .args(&args)
+        .arg(&temp)
+        .output()
+        .await
+        .map_err(|e| Message::new("command.run_failed").arg("program", "ffmpeg").arg("error", e))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&temp);
+        return Err(Message::new("common.ffmpeg_failed").arg("error", String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or_default()));
+    }
+
+    std::fs::rename(&temp, input).map_err(|e| Message::new("common.output_save_failed").arg("error", e))
+}
+
+// 2パスのloudnormで音量を正規化する
+async fn normalize(input: &Path, extension: &str, target: f64) -> Result<LoudnessResult, Message> {
+    let m = measure(input, target).await?;
+    let filter = format!(
+        "loudnorm=I={}:TP=-1.5:LRA=11:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true",
+        target, m.input_i, m.input_tp, m.input_lra, m.input_thresh, m.target_offset
+    );
+
+    // loudnormは内部で192kHzにリサンプリングするため元のサンプルレートに戻す
+    let sample_rate = match extension {
+        "opus" => 48000,
+        _ => probe_sample_rate(input).await.unwrap_or(48000),
+    };
+
+    let mut args: Vec<String> = ["-map", stream_map(extension), "-c", "copy", "-af", &filter, "-ar", &sample_rate.to_string()]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    args.extend(postprocess::audio_encoder_args(extension).iter().map(|arg| arg.to_string()));
+    rewrite(input, extension, args).await?;
+
+    Ok(LoudnessResult {
+        file: input.display().to_string(),
+        mode: "loudnorm".to_string(),
+        input_lufs: m.input_i,
+        input_true_peak: m.input_tp,
+        target_lufs: target,
+        gain_db: target - m.input_i,
+    })
+}
+
+// 音声は変更せずReplayGainタグだけを書き込む
+async fn write_replaygain(input: &Path, extension: &str) -> Result<LoudnessResult, Message> {
+    let m = measure(input, REPLAYGAIN_REFERENCE).await?;
+    let gain = REPLAYGAIN_REFERENCE - m.input_i;
+    let peak = 10f64.powf(m.input_tp / 20.0);
+
+    let mut tags = vec![
+        format!("REPLAYGAIN_TRACK_GAIN={:.2} dB", gain),
+        format!("REPLAYGAIN_TRACK_PEAK={:.6}", peak),
+    ];
+    if extension == "opus" {
+        // Opusのプレーヤーは-23 LUFS基準のQ7.8固定小数点値を参照する
+        tags.push(format!("R128_TRACK_GAIN={}", ((R128_REFERENCE - m.input_i) * 256.0).round() as i32));
+    }
+
+    let mut args: Vec<String> = ["-map", stream_map(extension), "-c", "copy"].iter().map(|arg| arg.to_string()).collect();
+    if extension == "mp3" {
+        args.extend(["-id3v2_version".to_string(), "3".to_string()]);
+    }
+    for tag in tags {
+        args.push("-metadata".to_string());
+        args.push(tag);
+    }
+    rewrite(input, extension, args).await?;
+
+    Ok(LoudnessResult {
+        file: input.display().to_string(),
+        mode: "replaygain".to_string(),
+        input_lufs: m.input_i,
+        input_true_peak: m.input_tp,
+        target_lufs: REPLAYGAIN_REFERENCE,
+        gain_db: gain,
+    })
+}
+
+// 抽出済みの音声ファイルにラウドネス処理を適用する
+pub async fn apply(input: &Path, mode: &str, target: Option<f64>) -> Result<LoudnessResult, Message> {
+    let extension = input
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !SUPPORTED_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(Message::new("loudness.unsupported_format").arg("extension", extension));
+    }
+
+    match mode {
+        "loudnorm" => normalize(input, &extension, target.unwrap_or(-16.0)).await,
+        "replaygain" => write_replaygain(input, &extension).await,
+        _ => Err(Message::new("loudness.unknown_mode").arg("mode", mode)),
+    }
+}
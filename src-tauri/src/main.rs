@@ -2,5 +2,10 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // サブコマンドが指定された場合はウィンドウを開かずにコマンドラインで実行する
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = necd_tauri_lib::cli::run(&args) {
+        std::process::exit(code);
+    }
     necd_tauri_lib::run()
 }
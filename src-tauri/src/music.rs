@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::Message;
+use crate::tools;
+
+// ミュージックモードのメタデータ変換ルール
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetadataRule {
+    // "parse" (--parse-metadata) または "replace" (--replace-in-metadata)
+    pub kind: String,
+    // parse: 元のフィールド名またはテンプレート / replace: 対象フィールド（カンマ区切り）
+    pub source: String,
+    // parse: 書き込み先のテンプレートまたは正規表現 / replace: 置換対象の正規表現
+    pub pattern: String,
+    // replace: 置換後の文字列
+    #[serde(default)]
+    pub replacement: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MusicMetadataPreview {
+    pub original_title: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub album_artist: String,
+    pub track: String,
+}
+
+fn parse(source: &str, pattern: &str) -> MetadataRule {
+    MetadataRule {
+        kind: "parse".to_string(),
+        source: source.to_string(),
+        pattern: pattern.to_string(),
+        replacement: String::new(),
+    }
+}
+
+fn replace(source: &str, pattern: &str, replacement: &str) -> MetadataRule {
+    MetadataRule {
+        kind: "replace".to_string(),
+        source: source.to_string(),
+        pattern: pattern.to_string(),
+        replacement: replacement.to_string(),
+    }
+}
+
+// 既定のルール
+// YouTube Musicのトラック情報 (track/artist/album) があればそれを優先し、
+// 無ければ「アーティスト - タイトル」形式の動画タイトルを分解する。
+// meta_*フィールドは--embed-metadataで書き込まれるタグを上書きする。
+pub fn default_rules() -> Vec<MetadataRule> {
+    vec![
+        parse("title", r"(?P<parsed_artist>.+?)\s+[-–—]\s+(?P<parsed_title>.+)"),
+        parse("%(artist,parsed_artist,uploader)s", "%(meta_artist)s"),
+        parse("%(track,parsed_title,title)s", "%(meta_title)s"),
+        replace(
+            "meta_title",
+            r"(?i)\s*[\(\[](?:official\s*)?(?:music\s*|lyric\s*)?(?:video|audio|mv|pv|lyrics?|visualizer|hd|4k)[\)\]]",
+            "",
+        ),
+        replace("meta_title", r"(?i)\s*【(?:MV|PV|Official[^】]*|公式[^】]*|歌詞[^】]*)】", ""),
+        replace("meta_artist", r"(?i)\s*(?:VEVO|- Topic|Official)$", ""),
+        parse("%(album,playlist_title|)s", "%(meta_album)s"),
+        parse("%(album_artist,playlist_uploader|)s", "%(meta_album_artist)s"),
+        parse("%(track_number,playlist_index|)s", "%(meta_track)s"),
+    ]
+}
+
+// ルールをyt-dlpの引数に変換する
+pub fn rule_args(rules: &[MetadataRule]) -> Vec<String> {
+    let mut args = Vec::new();
+    for rule in rules {
+        if rule.source.trim().is_empty() || rule.pattern.trim().is_empty() {
+            continue;
+        }
+        match rule.kind.as_str() {
+            "parse" => {
+                args.push("--parse-metadata".to_string());
+                args.push(format!("{}:{}", rule.source, rule.pattern));
+            }
+            "replace" => {
+                args.push("--replace-in-metadata".to_string());
+                args.push(rule.source.clone());
+                args.push(rule.pattern.clone());
+                args.push(rule.replacement.clone());
+            }
+            _ => {}
+        }
+    }
+    args
+}
+
+// 指定が無ければ既定のルールを使う
+pub fn effective_rules(rules: &[MetadataRule]) -> Vec<MetadataRule> {
+    if rules.is_empty() {
+        default_rules()
+    } else {
+        rules.to_vec()
+    }
+}
+
+// ダウンロードせずにルールの適用結果を確認する（プレイリストは先頭数件のみ）
+pub async fn preview(yt_dlp_path: &str, url: &str, rules: &[MetadataRule]) -> Result<Vec<MusicMetadataPreview>, Message> {
+    let mut cmd = tools::command(yt_dlp_path);
+    cmd.args(["--simulate", "--no-warnings", "--encoding", "utf-8", "--playlist-items", "1-5"])
+        .args(rule_args(&effective_rules(rules)))
+        .args([
+            "--print",
+            "%(title)s\t%(meta_title|)s\t%(meta_artist|)s\t%(meta_album|)s\t%(meta_album_artist|)s\t%(meta_track|)s",
+        ])
+        .args(["--", url])
+        .env("PYTHONIOENCODING", "utf-8");
+    let output = cmd.output()
+        .await
+        .map_err(|e| Message::new("command.run_failed").arg("program", "yt-dlp").arg("error", e))?;
+    if !output.status.success() {
+        return Err(Message::new("command.failed").arg("program", "yt-dlp").arg("error", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let previews = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut fields = line.split('\t').map(str::to_string);
+            let mut next = || fields.next().unwrap_or_default();
+            MusicMetadataPreview {
+                original_title: next(),
+                title: next(),
+                artist: next(),
+                album: next(),
+                album_artist: next(),
+                track: next(),
+            }
+        })
+        .collect();
+    Ok(previews)
+}
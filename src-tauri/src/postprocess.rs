@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::events::EventSink;
+use crate::i18n::Message;
+use crate::tools;
+
+// 音声のみとして扱う拡張子
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "opus", "flac", "m4a", "ogg", "wav", "aac"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostProcessProgress {
+    pub file: String,
+    pub preset: String,
+    pub message: String,
+    pub progress: Option<f64>,
+    pub is_error: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PresetInfo {
+    pub name: String,
+    pub label: String,
+    pub video_only: bool,
+}
+
+// 再エンコードのプリセット（表示名はpostprocess.preset.<name>のメッセージ）
+struct Preset {
+    name: &'static str,
+    // 出力拡張子（Noneなら元ファイルの拡張子を維持）
+    extension: Option<&'static str>,
+    video_only: bool,
+}
+
+const PRESETS: &[Preset] = &[
+    Preset { name: "h264_crf", extension: Some("mp4"), video_only: true },
+    Preset { name: "hevc", extension: Some("mp4"), video_only: true },
+    Preset { name: "audio_normalize", extension: None, video_only: false },
+    Preset { name: "device_iphone", extension: Some("mp4"), video_only: true },
+    Preset { name: "device_tv", extension: Some("mp4"), video_only: true },
+];
+
+impl Preset {
+    fn label(&self) -> String {
+        Message::new(&format!("postprocess.preset.{}", self.name)).text()
+    }
+}
+
+pub fn list_presets() -> Vec<PresetInfo> {
+    PRESETS
+        .iter()
+        .map(|preset| PresetInfo {
+            name: preset.name.to_string(),
+            label: preset.label(),
+            video_only: preset.video_only,
+        })
+        .collect()
+}
+
+// 拡張子ごとの音声エンコーダー
+pub(crate) fn audio_encoder_args(extension: &str) -> Vec<&'static str> {
+    match extension {
+        "mp3" => vec!["-c:a", "libmp3lame", "-q:a", "0"],
+        "opus" | "ogg" | "webm" | "mkv" => vec!["-c:a", "libopus", "-b:a", "160k"],
+        "flac" => vec!["-c:a", "flac"],
+        "wav" => vec!["-c:a", "pcm_s16le"],
+        _ => vec!["-c:a", "aac", "-b:a", "192k"],
+    }
+}
+
+// プリセットに対応するffmpegの引数
+fn preset_args(name: &str, extension: &str) -> Vec<&'static str> {
+    match name {
+        "h264_crf" => vec![
+            "-map", "0:v:0", "-map", "0:a?",
+            "-c:v", "libx264", "-crf", "20", "-preset", "medium", "-pix_fmt", "yuv420p",
+            "-c:a", "aac", "-b:a", "192k", "-movflags", "+faststart",
+        ],
+        "hevc" => vec![
+            "-map", "0:v:0", "-map", "0:a?",
+            "-c:v", "libx265", "-crf", "24", "-preset", "medium", "-tag:v", "hvc1",
+            "-c:a", "aac", "-b:a", "192k", "-movflags", "+faststart",
+        ],
+        "audio_normalize" => {
+            let mut args = vec!["-map", "0", "-c", "copy", "-af", "loudnorm=I=-16:TP=-1.5:LRA=11"];
+            args.extend(audio_encoder_args(extension));
+            args
+        }
+        "device_iphone" => vec![
+            "-map", "0:v:0", "-map", "0:a?",
+            "-c:v", "libx264", "-profile:v", "high", "-level", "4.1", "-pix_fmt", "yuv420p",
+            "-vf", "scale='min(1920,iw)':-2", "-crf", "21",
+            "-c:a", "aac", "-b:a", "160k", "-movflags", "+faststart",
+        ],
+        "device_tv" => vec![
+            "-map", "0:v:0", "-map", "0:a?",
+            "-c:v", "libx264", "-profile:v", "main", "-level", "4.0", "-pix_fmt", "yuv420p",
+            "-vf", "scale='min(1280,iw)':-2", "-crf", "22",
+            "-c:a", "aac", "-b:a", "128k", "-movflags", "+faststart",
+        ],
+        _ => Vec::new(),
+    }
+}
+
+// ffprobeで再生時間（秒）を取得
+async fn probe_duration(input: &Path) -> Option<f64> {
+    let mut cmd = tools::command(tools::ffprobe());
+    cmd.args(["-v", "error", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(input);
+    let output = cmd.output().await.ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse::<f64>().ok()
+}
+
+fn emit_progress<E: EventSink>(events: &E, file: &Path, preset: &str, message: String, progress: Option<f64>, is_error: bool) {
+    let payload = PostProcessProgress {
+        file: file.display().to_string(),
+        preset: preset.to_string(),
+        message,
+        progress,
+        is_error,
+    };
+    events.send("postprocess-progress", payload);
+}
+
+// ダウンロード済みファイルにプリセットを適用し、最終的なファイルのパスを返す
+pub async fn run_preset<E: EventSink>(
+    events: &E,
+    input: &Path,
+    preset_name: &str,
+    delete_original: bool,
+) -> Result<PathBuf, Message> {
+    let preset = PRESETS
+        .iter()
+        .find(|preset| preset.name == preset_name)
+        .ok_or_else(|| Message::new("postprocess.unknown_preset").arg("preset", preset_name))?;
+
+    let input_ext = input
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if preset.video_only && AUDIO_EXTENSIONS.contains(&input_ext.as_str()) {
+        return Err(Message::new("postprocess.video_only").arg("preset", preset.label()));
+    }
+
+    let stem = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .ok_or_else(|| Message::new("common.file_name_unavailable"))?;
+    let dir = input.parent().unwrap_or(Path::new("."));
+    let output_ext = preset.extension.unwrap_or(input_ext.as_str());
+    let output = if delete_original {
+        dir.join(format!("{}.{}", stem, output_ext))
+    } else {
+        dir.join(format!("{}_{}.{}", stem, preset.name, output_ext))
+    };
+    let temp = dir.join(format!("{}.necd-tmp.{}", stem, output_ext));
+
+    let duration = probe_duration(input).await;
+    emit_progress(events, input, preset.name, Message::new("postprocess.started").arg("preset", preset.label()).text(), Some(0.0), false);
+
+    let mut cmd = tools::command(tools::ffmpeg());
+    cmd.args(["-hide_banner", "-nostdin", "-y", "-i"])
+        .arg(input)
+        .args(preset_args(preset.name, &input_ext))
+        .args(["-progress", "pipe:1", "-nostats"])
+        .arg(&temp)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+
+    let mut child = cmd.spawn().map_err(|e| Message::new("command.run_failed").arg("program", "ffmpeg").arg("error", e))?;
+    let stdout = child.stdout.take().ok_or_else(|| Message::new("command.pipe_failed").arg("stream", "stdout"))?;
+    let stderr = child.stderr.take().ok_or_else(|| Message::new("command.pipe_failed").arg("stream", "stderr"))?;
+
+    // エラー時に表示するためstderrの末尾を保持
+    let stderr_handle = tokio::spawn(async move {
+        let mut reader = BufReader::new(stderr).lines();
+        let mut tail: Vec<String> = Vec::new();
+        while let Ok(Some(line)) = reader.next_line().await {
+            tail.push(line);
+            if tail.len() > 20 {
+                tail.remove(0);
+            }
+        }
+        tail
+    });
+
+    // -progressの出力 (key=value) から進捗を計算
+    let mut reader = BufReader::new(stdout).lines();
+    while let Ok(Some(line)) = reader.next_line().await {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key == "out_time_us" || key == "out_time_ms" {
+            if let (Ok(micros), Some(total)) = (value.trim().parse::<f64>(), duration) {
+                if total > 0.0 {
+                    let percent = (micros / 1_000_000.0 / total * 100.0).clamp(0.0, 100.0);
+                    emit_progress(events, input, preset.name, format!("[ENCODING]:{:.1}%", percent), Some(percent), false);
+                }
+            }
+        }
+    }
+
+    let status = child.wait().await.map_err(|e| Message::new("command.wait_failed").arg("error", e))?;
+    let tail = stderr_handle.await.unwrap_or_default();
+
+    if !status.success() {
+        let _ = std::fs::remove_file(&temp);
+        let reason = tail.last().cloned().unwrap_or_default();
+        emit_progress(events, input, preset.name, Message::new("postprocess.failed").arg("error", &reason).text(), None, true);
+        return Err(Message::new("common.ffmpeg_failed").arg("error", reason));
+    }
+
+    if delete_original {
+        std::fs::remove_file(input).map_err(|e| Message::new("postprocess.delete_original_failed").arg("error", e))?;
+    }
+    std::fs::rename(&temp, &output).map_err(|e| Message::new("common.output_save_failed").arg("error", e))?;
+
+    emit_progress(events, &output, preset.name, Message::new("postprocess.completed").text(), Some(100.0), false);
+    Ok(output)
+}
@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::args;
+use crate::i18n;
+use crate::live;
+use crate::tools;
+use crate::DownloadOptions;
+
+// 推定サイズの取得を諦めるまでの時間
+const PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+// 推定サイズに加える余裕 (bytes)
+const MARGIN_BYTES: u64 = 200 * 1024 * 1024;
+
+// ダウンロード前の確認で見つかった問題（UIが種類ごとに説明を出し分けられるようにする）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PreflightError {
+    // 保存先が指定されていない
+    EmptyDirectory,
+    // 保存先を作成できない
+    CreateFailed { path: String, reason: String },
+    // 保存先がフォルダではない
+    NotADirectory { path: String },
+    // 保存先に書き込めない
+    NotWritable { path: String, reason: String },
+    // 空き容量が足りない
+    InsufficientSpace {
+        path: String,
+        estimated_bytes: u64,
+        required_bytes: u64,
+        available_bytes: u64,
+    },
+}
+
+impl PreflightError {
+    pub fn localized(&self) -> i18n::Message {
+        match self {
+            PreflightError::EmptyDirectory => i18n::Message::new("preflight.empty_directory"),
+            PreflightError::CreateFailed { path, reason } => i18n::Message::new("preflight.create_failed")
+                .arg("path", path)
+                .arg("reason", reason),
+            PreflightError::NotADirectory { path } => i18n::Message::new("preflight.not_a_directory").arg("path", path),
+            PreflightError::NotWritable { path, reason } => i18n::Message::new("preflight.not_writable")
+                .arg("path", path)
+                .arg("reason", reason),
+            PreflightError::InsufficientSpace { path, required_bytes, available_bytes, .. } => {
+                i18n::Message::new("preflight.insufficient_space")
+                    .arg("path", path)
+                    .arg("required", format_size(*required_bytes))
+                    .arg("available", format_size(*available_bytes))
+            }
+        }
+    }
+
+    pub fn message(&self) -> String {
+        self.localized().text()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreflightReport {
+    pub directory: String,
+    // 保存先を新しく作成した
+    pub created: bool,
+    // yt-dlpが報告した推定サイズ（取得できなかった場合はNone）
+    pub estimated_bytes: Option<u64>,
+    pub required_bytes: Option<u64>,
+    pub available_bytes: Option<u64>,
+}
+
+pub fn format_size(bytes: u64) -> String {
+    let mb = bytes as f64 / 1024.0 / 1024.0;
+    if mb >= 1024.0 {
+        format!("{:.2} GB", mb / 1024.0)
+    } else {
+        format!("{:.1} MB", mb)
+    }
+}
+
+// yt-dlpが予測した出力ファイル
+pub struct ProbedItem {
+    // 後処理前のファイル名
+    pub filename: PathBuf,
+    pub filesize: u64,
+    // プレイリスト内の番号（単体の動画はNone）
+    pub playlist_index: Option<u32>,
+}
+
+// ダウンロードせずに出力ファイル名と推定サイズを取得する（プレイリストは全項目）
+pub async fn probe(yt_dlp_path: &str, options: &DownloadOptions) -> Option<Vec<ProbedItem>> {
+    // ライブ録画はサイズを事前に求められない
+    if live::is_recording(options) {
+        return None;
+    }
+    let mut cmd = tools::command(yt_dlp_path);
+    cmd.args(["--simulate", "--print", "%(filesize,filesize_approx|0)s\t%(playlist_index|)s\t%(filename)s"])
+        .args(args::build_download_args(options))
+        .env("PYTHONIOENCODING", "utf-8")
+        .stdin(std::process::Stdio::null());
+    let output = tokio::time::timeout(PROBE_TIMEOUT, cmd.output()).await.ok()?.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let items: Vec<ProbedItem> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().splitn(3, '\t');
+            let (size, index, filename) = (fields.next()?, fields.next()?, fields.next()?);
+            Some(ProbedItem {
+                filename: PathBuf::from(filename),
+                filesize: size.trim().parse::<f64>().map(|size| size as u64).unwrap_or(0),
+                playlist_index: index.trim().parse().ok(),
+            })
+        })
+        .collect();
+    if items.is_empty() {
+        None
+    } else {
+        Some(items)
+    }
+}
+
+// 保存先を確認し（無ければ作成し）、推定サイズに対して空き容量が足りるかを調べる
+pub fn check(options: &DownloadOptions, probed: Option<&[ProbedItem]>) -> Result<PreflightReport, PreflightError> {
+    let directory = options.output_directory.trim().to_string();
+    if directory.is_empty() {
+        return Err(PreflightError::EmptyDirectory);
+    }
+    let dir = Path::new(&directory);
+
+    let mut created = false;
+    if !dir.exists() {
+        std::fs::create_dir_all(dir).map_err(|e| PreflightError::CreateFailed {
+            path: directory.clone(),
+            reason: e.to_string(),
+        })?;
+        created = true;
+    }
+    if !dir.is_dir() {
+        return Err(PreflightError::NotADirectory { path: directory });
+    }
+
+    let probe = dir.join(format!(".necd-write-test-{}", std::process::id()));
+    std::fs::write(&probe, b"").map_err(|e| PreflightError::NotWritable {
+        path: directory.clone(),
+        reason: e.to_string(),
+    })?;
+    let _ = std::fs::remove_file(&probe);
+
+    let available_bytes = fs2::available_space(dir).ok();
+    let estimated_bytes = probed
+        .map(|items| items.iter().map(|item| item.filesize).sum::<u64>())
+        .filter(|total| *total > 0);
+
+    // 映像と音声の結合や音声の変換では元ファイルと出力が一時的に両方存在する
+    let required_bytes = estimated_bytes.map(|estimated| {
+        let factor = if args::VIDEO_FORMATS.contains(&options.format.as_str()) { 2.0 } else { 1.5 };
+        (estimated as f64 * factor) as u64 + MARGIN_BYTES
+    });
+
+    if let (Some(required), Some(available), Some(estimated)) = (required_bytes, available_bytes, estimated_bytes) {
+        if available < required {
+            return Err(PreflightError::InsufficientSpace {
+                path: directory,
+                estimated_bytes: estimated,
+                required_bytes: required,
+                available_bytes: available,
+            });
+        }
+    }
+
+    Ok(PreflightReport {
+        directory,
+        created,
+        estimated_bytes,
+        required_bytes,
+        available_bytes,
+    })
+}
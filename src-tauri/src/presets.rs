@@ -0,0 +1,310 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::i18n::Message;
+use crate::retry;
+use crate::settings;
+use crate::DownloadOptions;
+
+// 名前の最大文字数
+const MAX_NAME_LENGTH: usize = 64;
+// エクスポートするファイルの形式のバージョン
+const EXPORT_VERSION: u32 = 1;
+// プリセットに含めない項目（ダウンロードごとに決まるもの）
+const EXCLUDED_FIELDS: &[&str] = &["url", "job_id", "resume"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Preset {
+    pub name: String,
+    // URLを除くDownloadOptionsの項目（snake_case）
+    pub options: Map<String, Value>,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+// presets.jsonの内容
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PresetStore {
+    // CLI・HTTP API・リンク・クリップボードなどでプリセットを指定しなかった場合に使う
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub presets: Vec<Preset>,
+}
+
+// 共有用のファイルの内容
+#[derive(Debug, Serialize, Deserialize)]
+struct PresetExport {
+    version: u32,
+    presets: Vec<Preset>,
+}
+
+// 読み込めるファイルの形式（エクスポートしたファイル、プリセットの配列、1つのプリセット）
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PresetImport {
+    Export(PresetExport),
+    List(Vec<Preset>),
+    Single(Preset),
+}
+
+fn store_path() -> Result<PathBuf, Message> {
+    Ok(dirs::config_dir()
+        .ok_or_else(|| Message::new("common.config_dir_unavailable"))?
+        .join("necd-tauri")
+        .join("presets.json"))
+}
+
+pub fn load() -> PresetStore {
+    store_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(store: &PresetStore) -> Result<(), Message> {
+    let path = store_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| Message::new("common.config_dir_create_failed").arg("error", e))?;
+    }
+    let json = serde_json::to_string_pretty(store).map_err(|e| Message::new("preset.serialize_failed").arg("error", e))?;
+    fs::write(path, json).map_err(|e| Message::new("preset.save_failed").arg("error", e))
+}
+
+fn not_found(name: &str) -> Message {
+    Message::new("preset.not_found").arg("preset", name)
+}
+
+fn validate_name(name: &str) -> Result<String, Message> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH || name.chars().any(char::is_control) {
+        return Err(Message::new("preset.invalid_name").arg("preset", name));
+    }
+    Ok(name.to_string())
+}
+
+// ダウンロードごとの項目を除き、DownloadOptionsとして読めることを確かめる（足りない項目は設定のデフォルト値で補う）
+fn to_preset_options(options: Map<String, Value>) -> Result<Map<String, Value>, Message> {
+    let Value::Object(mut check) = settings::to_snake_case(settings::default_settings()) else {
+        return Err(Message::new("options.invalid").arg("error", "settings"));
+    };
+    check.extend(options.clone());
+    check.insert("url".to_string(), Value::String(String::new()));
+    serde_json::from_value::<DownloadOptions>(Value::Object(check)).map_err(|e| Message::new("options.invalid").arg("error", e))?;
+    Ok(options
+        .into_iter()
+        .filter(|(key, _)| !EXCLUDED_FIELDS.contains(&key.as_str()))
+        .collect())
+}
+
+fn from_download_options(options: &DownloadOptions) -> Result<Map<String, Value>, Message> {
+    match serde_json::to_value(options) {
+        Ok(Value::Object(map)) => to_preset_options(map),
+        _ => Err(Message::new("options.invalid").arg("error", "options")),
+    }
+}
+
+fn find<'a>(store: &'a mut PresetStore, name: &str) -> Result<&'a mut Preset, Message> {
+    store.presets.iter_mut().find(|preset| preset.name == name).ok_or_else(|| not_found(name))
+}
+
+fn exists(store: &PresetStore, name: &str) -> bool {
+    store.presets.iter().any(|preset| preset.name == name)
+}
+
+// 使われていない名前（"名前 (2)"のように番号を付ける）
+fn unique_name(store: &PresetStore, base: &str) -> String {
+    if !exists(store, base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|number| format!("{} ({})", base, number))
+        .find(|name| !exists(store, name))
+        .unwrap_or_default()
+}
+
+pub fn create(name: &str, options: &DownloadOptions) -> Result<Preset, Message> {
+    let name = validate_name(name)?;
+    let mut store = load();
+    if exists(&store, &name) {
+        return Err(Message::new("preset.already_exists").arg("preset", &name));
+    }
+    let now = retry::now();
+    let preset = Preset {
+        name,
+        options: from_download_options(options)?,
+        created_at: now,
+        updated_at: now,
+    };
+    store.presets.push(preset.clone());
+    save(&store)?;
+    Ok(preset)
+}
+
+pub fn update(name: &str, options: &DownloadOptions) -> Result<Preset, Message> {
+    let mut store = load();
+    let options = from_download_options(options)?;
+    let preset = find(&mut store, name)?;
+    preset.options = options;
+    preset.updated_at = retry::now();
+    let preset = preset.clone();
+    save(&store)?;
+    Ok(preset)
+}
+
+pub fn rename(name: &str, new_name: &str) -> Result<Preset, Message> {
+    let new_name = validate_name(new_name)?;
+    let mut store = load();
+    if new_name != name && exists(&store, &new_name) {
+        return Err(Message::new("preset.already_exists").arg("preset", &new_name));
+    }
+    let preset = find(&mut store, name)?;
+    preset.name = new_name.clone();
+    preset.updated_at = retry::now();
+    let preset = preset.clone();
+    if store.default.as_deref() == Some(name) {
+        store.default = Some(new_name);
+    }
+    save(&store)?;
+    Ok(preset)
+}
+
+pub fn delete(name: &str) -> Result<(), Message> {
+    let mut store = load();
+    if !exists(&store, name) {
+        return Err(not_found(name));
+    }
+    store.presets.retain(|preset| preset.name != name);
+    if store.default.as_deref() == Some(name) {
+        store.default = None;
+    }
+    save(&store)
+}
+
+// new_nameを省略すると"名前 (2)"などの名前を付ける
+pub fn duplicate(name: &str, new_name: Option<&str>) -> Result<Preset, Message> {
+    let mut store = load();
+    let source = find(&mut store, name)?.clone();
+    let new_name = match new_name {
+        Some(new_name) => {
+            let new_name = validate_name(new_name)?;
+            if exists(&store, &new_name) {
+                return Err(Message::new("preset.already_exists").arg("preset", &new_name));
+            }
+            new_name
+        }
+        None => unique_name(&store, &source.name),
+    };
+    let now = retry::now();
+    let preset = Preset {
+        name: new_name,
+        options: source.options,
+        created_at: now,
+        updated_at: now,
+    };
+    store.presets.push(preset.clone());
+    save(&store)?;
+    Ok(preset)
+}
+
+// Noneで既定のプリセットを解除する
+pub fn set_default(name: Option<&str>) -> Result<PresetStore, Message> {
+    let mut store = load();
+    if let Some(name) = name {
+        if !exists(&store, name) {
+            return Err(not_found(name));
+        }
+    }
+    store.default = name.map(str::to_string);
+    save(&store)?;
+    Ok(store)
+}
+
+// namesを省略するとすべてのプリセットを書き出す
+pub fn export(destination: &str, names: Option<&[String]>) -> Result<String, Message> {
+    let store = load();
+    let presets: Vec<Preset> = match names {
+        Some(names) => {
+            if let Some(missing) = names.iter().find(|name| !exists(&store, name)) {
+                return Err(not_found(missing));
+            }
+            store.presets.into_iter().filter(|preset| names.contains(&preset.name)).collect()
+        }
+        None => store.presets,
+    };
+    let export = PresetExport {
+        version: EXPORT_VERSION,
+        presets,
+    };
+    let json = serde_json::to_string_pretty(&export).map_err(|e| Message::new("preset.serialize_failed").arg("error", e))?;
+    fs::write(destination, json).map_err(|e| Message::new("common.write_failed").arg("error", e))?;
+    Ok(destination.to_string())
+}
+
+// 同じ名前がある場合、overwriteなら置き換え、それ以外は番号を付けて追加する。追加した名前を返す
+pub fn import(path: &str, overwrite: bool) -> Result<Vec<String>, Message> {
+    let content = fs::read_to_string(path).map_err(|e| Message::new("common.read_failed").arg("error", e))?;
+    let imported = match serde_json::from_str::<PresetImport>(content.trim_start_matches('\u{feff}')) {
+        Ok(PresetImport::Export(export)) => export.presets,
+        Ok(PresetImport::List(presets)) => presets,
+        Ok(PresetImport::Single(preset)) => vec![preset],
+        Err(e) => return Err(Message::new("preset.invalid_file").arg("error", e)),
+    };
+
+    // すべて検証してから保存する
+    let mut validated = Vec::new();
+    for preset in imported {
+        let name = validate_name(&preset.name)?;
+        let options = to_preset_options(preset.options).map_err(|e| format!("{}: {}", name, e))?;
+        validated.push((name, options));
+    }
+
+    let mut store = load();
+    let now = retry::now();
+    let mut names = Vec::new();
+    for (name, options) in validated {
+        if overwrite {
+            if let Ok(existing) = find(&mut store, &name) {
+                existing.options = options;
+                existing.updated_at = now;
+                names.push(name);
+                continue;
+            }
+        }
+        let name = unique_name(&store, &name);
+        store.presets.push(Preset {
+            name: name.clone(),
+            options,
+            created_at: now,
+            updated_at: now,
+        });
+        names.push(name);
+    }
+    save(&store)?;
+    Ok(names)
+}
+
+// ダウンロードに使う項目（名前の指定が無ければ既定のプリセット、既定も無ければ空）
+// 保存したプリセットに無い名前は組み込みのプリセット（video, audio, music）を探す
+pub fn resolve(name: Option<&str>) -> Result<Map<String, Value>, Message> {
+    let store = load();
+    let name = match name.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(name) => name,
+        None => {
+            return Ok(store
+                .default
+                .and_then(|default| store.presets.into_iter().find(|preset| preset.name == default))
+                .map(|preset| preset.options)
+                .unwrap_or_default())
+        }
+    };
+    if let Some(preset) = store.presets.into_iter().find(|preset| preset.name == name) {
+        return Ok(preset.options);
+    }
+    settings::builtin_preset(name).ok_or_else(|| not_found(name))
+}
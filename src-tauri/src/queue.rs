@@ -0,0 +1,279 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use tauri::AppHandle;
+use tokio::sync::broadcast;
+
+use crate::events::EventSink;
+use crate::i18n::Message;
+use crate::jobs;
+use crate::retry;
+use crate::urls;
+use crate::{DownloadOptions, DownloadProgress};
+
+// 同時に実行するダウンロード数の既定値（設定のmaxConcurrentDownloads）
+const DEFAULT_MAX_CONCURRENT: usize = 2;
+// 終了したジョブの記録を残す件数
+const MAX_FINISHED: usize = 100;
+
+// ジョブの状態（IPCコマンドとHTTP APIで共通）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobInfo {
+    pub job_id: String,
+    pub url: String,
+    pub format: String,
+    // 追加元 ("app", "api", "cli")
+    pub source: String,
+    // "queued", "running", "cancelling", "completed", "failed", "cancelled"
+    pub status: String,
+    pub progress: Option<f64>,
+    pub message: Option<String>,
+    pub files: Vec<String>,
+    // 動画とプレイリストの両方を指すURL（playlist_modeで選んだ方をダウンロードする）
+    pub ambiguous: bool,
+    pub playlist_mode: bool,
+    // 追加・終了時刻 (UNIX秒)
+    pub created_at: u64,
+    pub finished_at: Option<u64>,
+}
+
+// WebSocketなどに配信するイベント
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobEvent {
+    pub job_id: String,
+    pub event: String,
+    pub payload: Value,
+}
+
+struct Pending {
+    job_id: String,
+    options: DownloadOptions,
+}
+
+static JOBS: Mutex<Vec<JobInfo>> = Mutex::new(Vec::new());
+static PENDING: Mutex<VecDeque<Pending>> = Mutex::new(VecDeque::new());
+// 開始の判定を同時に行わないようにする
+static PUMP: Mutex<()> = Mutex::new(());
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static BUS: OnceLock<broadcast::Sender<JobEvent>> = OnceLock::new();
+
+fn bus() -> &'static broadcast::Sender<JobEvent> {
+    BUS.get_or_init(|| broadcast::channel(256).0)
+}
+
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+pub fn subscribe() -> broadcast::Receiver<JobEvent> {
+    bus().subscribe()
+}
+
+fn publish(job_id: &str, event: &str, payload: Value) {
+    let _ = bus().send(JobEvent {
+        job_id: job_id.to_string(),
+        event: event.to_string(),
+        payload,
+    });
+}
+
+// ジョブのイベントを本来の送り先に加えて購読者にも配信する
+#[derive(Clone)]
+pub struct JobSink<E> {
+    inner: E,
+    job_id: String,
+}
+
+impl<E: EventSink> JobSink<E> {
+    pub fn new(inner: E, job_id: &str) -> JobSink<E> {
+        JobSink {
+            inner,
+            job_id: job_id.to_string(),
+        }
+    }
+}
+
+impl<E: EventSink> EventSink for JobSink<E> {
+    fn send<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Ok(value) = serde_json::to_value(&payload) {
+            let downloading = value
+                .get("message")
+                .and_then(Value::as_str)
+                .is_some_and(|message| message.contains("[DOWNLOADING]:"));
+            if let (true, Some(progress)) = (downloading, value.get("progress").and_then(Value::as_f64)) {
+                let mut jobs = JOBS.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(job) = jobs.iter_mut().find(|job| job.job_id == self.job_id) {
+                    job.progress = Some(progress);
+                }
+            }
+            publish(&self.job_id, event, value);
+        }
+        self.inner.send(event, payload);
+    }
+}
+
+fn max_concurrent() -> usize {
+    crate::settings::saved_settings()
+        .get("maxConcurrentDownloads")
+        .and_then(Value::as_u64)
+        .filter(|max| *max > 0)
+        .map(|max| max as usize)
+        .unwrap_or(DEFAULT_MAX_CONCURRENT)
+}
+
+// 状態を変更し、GUIと購読者に通知する
+fn update(job_id: &str, change: impl FnOnce(&mut JobInfo)) -> Option<JobInfo> {
+    let job = {
+        let mut jobs = JOBS.lock().unwrap_or_else(|e| e.into_inner());
+        let job = jobs.iter_mut().find(|job| job.job_id == job_id)?;
+        change(job);
+        job.clone()
+    };
+    notify(&job);
+    Some(job)
+}
+
+fn notify(job: &JobInfo) {
+    if let Some(app) = APP.get() {
+        app.send("job-updated", job.clone());
+    }
+    publish(&job.job_id, "job-updated", serde_json::to_value(job).unwrap_or_default());
+}
+
+// ジョブを記録する（同じIDの記録は置き換える）
+pub fn track(job_id: &str, options: &DownloadOptions, source: &str, status: &str) -> JobInfo {
+    let job = JobInfo {
+        job_id: job_id.to_string(),
+        url: options.url.clone(),
+        format: options.format.clone(),
+        source: source.to_string(),
+        status: status.to_string(),
+        progress: None,
+        message: None,
+        files: Vec::new(),
+        ambiguous: urls::classify(&options.url).is_ok_and(|classified| classified.ambiguous),
+        playlist_mode: options.playlist_mode,
+        created_at: retry::now(),
+        finished_at: None,
+    };
+    {
+        let mut jobs = JOBS.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.retain(|existing| existing.job_id != job_id);
+        jobs.push(job.clone());
+
+        // 古い終了済みの記録を捨てる
+        let finished = jobs.iter().filter(|job| job.finished_at.is_some()).count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED);
+        jobs.retain(|job| {
+            if excess > 0 && job.finished_at.is_some() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+    notify(&job);
+    job
+}
+
+// ダウンロードの結果を記録し、空いた枠で待機中のジョブを開始する
+pub fn finish(job_id: &str, result: &Result<DownloadProgress, Message>) {
+    jobs::clear_deferred_stop(job_id);
+    update(job_id, |job| {
+        let cancelled = job.status == "cancelling";
+        match result {
+            Ok(progress) => {
+                job.status = if (cancelled || progress.stopped) && progress.files.is_empty() {
+                    "cancelled"
+                } else if progress.is_error {
+                    "failed"
+                } else {
+                    "completed"
+                }
+                .to_string();
+                job.message = Some(progress.message.clone());
+                job.files = progress.files.clone();
+                if !progress.is_error {
+                    job.progress = Some(100.0);
+                }
+            }
+            Err(e) => {
+                job.status = if cancelled { "cancelled" } else { "failed" }.to_string();
+                job.message = Some(e.text());
+            }
+        }
+        job.finished_at = Some(retry::now());
+    });
+    pump();
+}
+
+pub fn list() -> Vec<JobInfo> {
+    JOBS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn get(job_id: &str) -> Option<JobInfo> {
+    JOBS.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find(|job| job.job_id == job_id)
+        .cloned()
+}
+
+// キューに追加し、空きがあればすぐに開始する
+pub fn enqueue(mut options: DownloadOptions, source: &str) -> JobInfo {
+    let job_id = options.job_id.clone().filter(|id| !id.is_empty()).unwrap_or_else(jobs::generate_id);
+    options.job_id = Some(job_id.clone());
+    let job = track(&job_id, &options, source, "queued");
+    PENDING.lock().unwrap_or_else(|e| e.into_inner()).push_back(Pending { job_id, options });
+    pump();
+    job
+}
+
+// 実行数の上限まで待機中のジョブを開始する
+fn pump() {
+    let Some(app) = APP.get().cloned() else {
+        return;
+    };
+    let max = max_concurrent();
+    let _guard = PUMP.lock().unwrap_or_else(|e| e.into_inner());
+    loop {
+        let next = {
+            let running = list().iter().filter(|job| matches!(job.status.as_str(), "running" | "cancelling")).count();
+            if running >= max {
+                return;
+            }
+            PENDING.lock().unwrap_or_else(|e| e.into_inner()).pop_front()
+        };
+        let Some(Pending { job_id, options }) = next else {
+            return;
+        };
+        update(&job_id, |job| job.status = "running".to_string());
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let result = crate::download(&JobSink::new(app, &job_id), options).await;
+            finish(&job_id, &result);
+        });
+    }
+}
+
+// 待機中のジョブは取り除き、実行中のジョブは停止する
+pub fn cancel(job_id: &str) -> Result<JobInfo, Message> {
+    let job = get(job_id).ok_or_else(|| Message::new("job.not_found").arg("job", job_id))?;
+    match job.status.as_str() {
+        "queued" => {
+            PENDING.lock().unwrap_or_else(|e| e.into_inner()).retain(|pending| pending.job_id != job_id);
+            update(job_id, |job| {
+                job.status = "cancelled".to_string();
+                job.finished_at = Some(retry::now());
+            })
+            .ok_or_else(|| Message::new("job.not_found").arg("job", job_id))
+        }
+        "running" => {
+            jobs::request_stop_or_defer(job_id);
+            update(job_id, |job| job.status = "cancelling".to_string()).ok_or_else(|| Message::new("job.not_found").arg("job", job_id))
+        }
+        _ => Ok(job),
+    }
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::i18n::Message;
+use crate::jobs;
+use crate::preflight::ProbedItem;
+use crate::DownloadOptions;
+
+// 中断されたダウンロードの途中ファイルとみなす拡張子など
+const PARTIAL_MARKERS: &[&str] = &[".part", ".ytdl", ".temp"];
+
+// 実行中のジョブの状態（jobs/<ジョブID>.json に保存）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedJob {
+    pub job_id: String,
+    // 開始時刻 (UNIX秒)
+    pub started_at: u64,
+    pub options: DownloadOptions,
+    // 予測した出力ファイル（途中ファイルの削除に使う）
+    #[serde(default)]
+    pub expected_files: Vec<String>,
+    // yt-dlpが書き込みを始めたファイル（[download] Destination:の行から記録する）
+    #[serde(default)]
+    pub recorded_files: Vec<String>,
+}
+
+// UIに表示する中断されたジョブ
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InterruptedJob {
+    pub job_id: String,
+    pub started_at: u64,
+    pub url: String,
+    pub format: String,
+    pub output_directory: String,
+    // 残っている途中ファイル
+    pub partial_files: Vec<String>,
+}
+
+fn jobs_dir() -> Result<PathBuf, Message> {
+    Ok(dirs::data_dir()
+        .ok_or_else(|| Message::new("common.data_dir_unavailable"))?
+        .join("necd-tauri")
+        .join("jobs"))
+}
+
+fn job_path(job_id: &str) -> Result<PathBuf, Message> {
+    // ジョブIDはファイル名に使うため区切り文字を含めない
+    if job_id.is_empty() || job_id.contains(['/', '\\']) || job_id.contains("..") {
+        return Err(Message::new("job.invalid_id").arg("job", job_id));
+    }
+    Ok(jobs_dir()?.join(format!("{}.json", job_id)))
+}
+
+// 開始時に状態を書き出す
+pub fn save(job_id: &str, options: &DownloadOptions, probed: Option<&[ProbedItem]>) -> Result<(), Message> {
+    let job = SavedJob {
+        job_id: job_id.to_string(),
+        started_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        options: options.clone(),
+        expected_files: probed
+            .unwrap_or_default()
+            .iter()
+            .map(|item| item.filename.display().to_string())
+            .collect(),
+        recorded_files: Vec::new(),
+    };
+    fs::create_dir_all(jobs_dir()?).map_err(|e| Message::new("common.dir_create_failed").arg("error", e))?;
+    let json = serde_json::to_string_pretty(&job).map_err(|e| Message::new("job.serialize_failed").arg("error", e))?;
+    fs::write(job_path(job_id)?, json).map_err(|e| Message::new("job.state_save_failed").arg("error", e))
+}
+
+// yt-dlpが書き込みを始めたファイルを記録する（予測できなかった場合も途中ファイルを特定できるように）
+pub fn record_file(job_id: &str, file: &str) {
+    let Ok(mut job) = load(job_id) else {
+        return;
+    };
+    if job.recorded_files.iter().any(|recorded| recorded == file) {
+        return;
+    }
+    job.recorded_files.push(file.to_string());
+    if let (Ok(path), Ok(json)) = (job_path(job_id), serde_json::to_string_pretty(&job)) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// 終了したら（成功・失敗を問わず）状態を削除する
+pub fn remove(job_id: &str) {
+    if let Ok(path) = job_path(job_id) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+pub fn load(job_id: &str) -> Result<SavedJob, Message> {
+    let content = fs::read_to_string(job_path(job_id)?)
+        .map_err(|_| Message::new("job.interrupted_not_found").arg("job", job_id))?;
+    serde_json::from_str(&content).map_err(|e| Message::new("job.state_load_failed").arg("error", e))
+}
+
+fn is_partial(name: &str) -> bool {
+    PARTIAL_MARKERS.iter().any(|marker| name.ends_with(marker)) || name.contains(".part-Frag")
+}
+
+// 途中ファイルを探す
+// 予測したファイル名とyt-dlpが書き込んだファイル名に続く途中ファイルのみを対象にする
+// （同じ保存先の他のジョブのファイルを消さないよう、名前が分からない場合は何も返さない）
+fn partial_files(job: &SavedJob) -> Vec<PathBuf> {
+    let mut found: Vec<PathBuf> = Vec::new();
+    for file in job.expected_files.iter().chain(&job.recorded_files) {
+        let path = Path::new(file);
+        let Some(stem) = path.file_stem().map(|stem| format!("{}.", stem.to_string_lossy())) else {
+            continue;
+        };
+        let Ok(entries) = fs::read_dir(path.parent().unwrap_or(Path::new("."))) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = entry.path();
+            if is_partial(&name) && name.starts_with(&stem) && !found.contains(&path) {
+                found.push(path);
+            }
+        }
+    }
+    found
+}
+
+// 前回の終了時に実行中だったジョブ（このプロセスで実行中のものは除く）
+pub fn list_interrupted() -> Vec<InterruptedJob> {
+    let Some(entries) = jobs_dir().ok().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut jobs: Vec<InterruptedJob> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|content| serde_json::from_str::<SavedJob>(&content).ok())
+        .filter(|job| !jobs::is_running(&job.job_id))
+        .map(|job| InterruptedJob {
+            partial_files: partial_files(&job).iter().map(|path| path.display().to_string()).collect(),
+            job_id: job.job_id,
+            started_at: job.started_at,
+            url: job.options.url,
+            format: job.options.format,
+            output_directory: job.options.output_directory,
+        })
+        .collect();
+    jobs.sort_by_key(|job| job.started_at);
+    jobs
+}
+
+// 中断されたジョブを破棄し、途中ファイルを削除する
+pub fn discard(job_id: &str) -> Result<Vec<String>, Message> {
+    if jobs::is_running(job_id) {
+        return Err(Message::new("job.discard_running").arg("job", job_id));
+    }
+    let job = load(job_id)?;
+    let mut removed = Vec::new();
+    for path in partial_files(&job) {
+        if fs::remove_file(&path).is_ok() {
+            removed.push(path.display().to_string());
+        }
+    }
+    remove(job_id);
+    Ok(removed)
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::errors::DownloadError;
+use crate::DownloadOptions;
+
+// アプリ側で再実行する回数の既定値（初回を含む）
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+// 最初の再実行までの待ち時間の既定値（秒）
+const DEFAULT_BACKOFF_SECONDS: u64 = 5;
+// 待ち時間の上限（秒）
+const MAX_BACKOFF_SECONDS: u64 = 300;
+
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    base_delay: u64,
+}
+
+// 1回分の実行結果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AttemptRecord {
+    pub job_id: String,
+    pub attempt: u32,
+    pub max_attempts: u32,
+    // 開始時刻 (UNIX秒)
+    pub started_at: u64,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    // 失敗の原因と判定したエラー
+    pub error: Option<DownloadError>,
+    // 次の実行までの待ち時間（再実行しない場合はNone）
+    pub retry_in_seconds: Option<u64>,
+}
+
+impl RetryPolicy {
+    pub fn from_options(options: &DownloadOptions) -> Self {
+        RetryPolicy {
+            max_attempts: options.max_attempts.filter(|attempts| *attempts > 0).unwrap_or(DEFAULT_MAX_ATTEMPTS),
+            base_delay: options.retry_backoff_seconds.unwrap_or(DEFAULT_BACKOFF_SECONDS),
+        }
+    }
+
+    // attempt回目が失敗した後の待ち時間（指数的に延ばす）
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_secs(self.base_delay.saturating_mul(factor).min(MAX_BACKOFF_SECONDS))
+    }
+}
+
+// 最後に出たエラーが一時的なものなら再実行する
+pub fn is_transient(errors: &[DownloadError]) -> bool {
+    errors.last().is_some_and(|error| error.retryable)
+}
+
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,361 @@
+use axum::extract::ws::{Message as WsMessage, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, Request};
+use axum::http::{header, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Mutex;
+use tokio::sync::{broadcast, oneshot};
+
+use crate::i18n::Message;
+use crate::queue;
+use crate::settings;
+use crate::urls;
+
+// 既定の待ち受けポート（127.0.0.1のみ）
+const DEFAULT_PORT: u16 = 48651;
+// 停止時に接続中のリクエストやWebSocketの終了を待つ時間
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+// api.jsonに保存する設定（settings.jsonはフロントエンドが丸ごと書き換えるため分ける）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiServerStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    pub token: String,
+    pub url: String,
+    // 起動に失敗した場合の理由（ポートが使用中など）
+    pub error: Option<Message>,
+}
+
+#[derive(Deserialize)]
+struct EnqueueRequest {
+    url: String,
+    // DownloadOptionsの項目（設定と同じcamelCase）。指定しない項目は保存された設定を使う
+    #[serde(default)]
+    options: Map<String, Value>,
+    // プリセット名（省略すると既定のプリセット）。optionsの項目はプリセットより優先する
+    #[serde(default)]
+    preset: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EventsQuery {
+    // 指定した場合はそのジョブのイベントのみ送る
+    job_id: Option<String>,
+}
+
+struct Running {
+    port: u16,
+    token: String,
+    shutdown: oneshot::Sender<()>,
+    // 待ち受けを閉じるまで待つためのハンドル
+    handle: tauri::async_runtime::JoinHandle<()>,
+}
+
+static RUNNING: Mutex<Option<Running>> = Mutex::new(None);
+static LAST_ERROR: Mutex<Option<Message>> = Mutex::new(None);
+
+fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+fn config_path() -> Result<std::path::PathBuf, Message> {
+    Ok(dirs::config_dir()
+        .ok_or_else(|| Message::new("common.config_dir_unavailable"))?
+        .join("necd-tauri")
+        .join("api.json"))
+}
+
+fn load_config() -> ApiConfig {
+    config_path()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or(ApiConfig {
+            enabled: false,
+            port: DEFAULT_PORT,
+            token: String::new(),
+        })
+}
+
+fn save_config(config: &ApiConfig) -> Result<(), Message> {
+    let path = config_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| Message::new("common.config_dir_create_failed").arg("error", e))?;
+    }
+    let json = serde_json::to_string_pretty(config).map_err(|e| Message::new("settings.serialize_failed").arg("error", e))?;
+    std::fs::write(path, json).map_err(|e| Message::new("settings.save_failed").arg("error", e))
+}
+
+fn generate_token() -> Result<String, Message> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| Message::new("server.token_failed").arg("error", e))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+// 比較にかかる時間からトークンを推測されないようにする
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn current_token() -> Option<String> {
+    RUNNING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|running| running.token.clone())
+}
+
+// errorは表示用の文、messageは翻訳用のキーと引数
+fn error_response(status: StatusCode, message: impl Into<Message>) -> Response {
+    let message = message.into();
+    (status, Json(serde_json::json!({ "error": message.text(), "message": message }))).into_response()
+}
+
+// ブラウザ拡張機能から呼べるようにする（認証はトークンで行う）
+fn with_cors(mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static("GET, POST, DELETE, OPTIONS"));
+    headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, HeaderValue::from_static("Authorization, Content-Type"));
+    response
+}
+
+// Authorization: Bearer <トークン> またはクエリの?token=（WebSocketはヘッダーを付けられないため）
+async fn authenticate(request: Request, next: Next) -> Response {
+    if request.method() == Method::OPTIONS {
+        return with_cors(StatusCode::NO_CONTENT.into_response());
+    }
+    if request.uri().path() == "/api/v1/health" {
+        return with_cors(next.run(request).await);
+    }
+
+    let Some(expected) = current_token() else {
+        return with_cors(error_response(StatusCode::SERVICE_UNAVAILABLE, "server is stopping"));
+    };
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .map(str::to_string);
+    let query = request.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+            .map(str::to_string)
+    });
+    let authorized = bearer.or(query).is_some_and(|token| token_matches(&token, &expected));
+    if !authorized {
+        return with_cors(error_response(StatusCode::UNAUTHORIZED, "invalid or missing token"));
+    }
+    with_cors(next.run(request).await)
+}
+
+async fn health() -> Json<Value> {
+    Json(serde_json::json!({
+        "app": "necd-tauri",
+        "version": env!("CARGO_PKG_VERSION"),
+    }))
+}
+
+async fn list_jobs() -> Json<Vec<queue::JobInfo>> {
+    Json(queue::list())
+}
+
+async fn get_job(Path(job_id): Path<String>) -> Response {
+    match queue::get(&job_id) {
+        Some(job) => Json(job).into_response(),
+        None => error_response(StatusCode::NOT_FOUND, format!("job not found: {}", job_id)),
+    }
+}
+
+async fn enqueue(Json(request): Json<EnqueueRequest>) -> Response {
+    if request.url.trim().is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "url is required");
+    }
+    if !urls::is_web_url(&request.url) {
+        return error_response(StatusCode::BAD_REQUEST, "url must be an http(s) URL");
+    }
+    let Value::Object(overrides) = settings::to_snake_case(Value::Object(request.options)) else {
+        return error_response(StatusCode::BAD_REQUEST, "options must be an object");
+    };
+    match settings::options_for(request.url.trim(), request.preset.as_deref(), &overrides).await {
+        Ok(options) => (StatusCode::CREATED, Json(queue::enqueue(options, "api"))).into_response(),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+async fn cancel(Path(job_id): Path<String>) -> Response {
+    match queue::cancel(&job_id) {
+        Ok(job) => Json(job).into_response(),
+        Err(e) => error_response(StatusCode::NOT_FOUND, e),
+    }
+}
+
+async fn events(ws: WebSocketUpgrade, Query(query): Query<EventsQuery>) -> Response {
+    ws.on_upgrade(move |socket| stream_events(socket, query.job_id))
+}
+
+// ジョブのイベントをJSONで送り続ける（接続時に現在のジョブ一覧を送る）
+async fn stream_events(mut socket: WebSocket, job_id: Option<String>) {
+    let mut receiver = queue::subscribe();
+    let snapshot = serde_json::json!({ "event": "jobs", "payload": queue::list() });
+    if socket.send(WsMessage::Text(snapshot.to_string().into())).await.is_err() {
+        return;
+    }
+    loop {
+        tokio::select! {
+            event = receiver.recv() => match event {
+                Ok(event) => {
+                    if job_id.as_ref().is_some_and(|job_id| *job_id != event.job_id) {
+                        continue;
+                    }
+                    let Ok(json) = serde_json::to_string(&event) else {
+                        continue;
+                    };
+                    if socket.send(WsMessage::Text(json.into())).await.is_err() {
+                        break;
+                    }
+                }
+                // 受信が追いつかなかった分は捨てる
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            },
+        }
+    }
+}
+
+fn router() -> Router {
+    Router::new()
+        .route("/api/v1/health", get(health))
+        .route("/api/v1/jobs", get(list_jobs).post(enqueue))
+        .route("/api/v1/jobs/{job_id}", get(get_job).delete(cancel))
+        .route("/api/v1/events", get(events))
+        .layer(middleware::from_fn(authenticate))
+}
+
+// 停止を伝え、ポートが解放されるまで待つ（同じポートで起動し直してもAddrInUseにならないように）
+async fn stop() {
+    let Some(running) = RUNNING.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    let _ = running.shutdown.send(());
+    let handle = running.handle;
+    // WebSocketなどの接続が閉じない場合は打ち切る
+    let abort = handle.inner().abort_handle();
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, handle).await.is_err() {
+        abort.abort();
+    }
+}
+
+async fn start(config: &ApiConfig) -> Result<(), Message> {
+    stop().await;
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", config.port))
+        .await
+        .map_err(|e| Message::new("server.listen_failed").arg("port", config.port).arg("error", e))?;
+    let (shutdown, stopped) = oneshot::channel::<()>();
+    let handle = tauri::async_runtime::spawn(async move {
+        let result = axum::serve(listener, router())
+            .with_graceful_shutdown(async {
+                let _ = stopped.await;
+            })
+            .await;
+        if let Err(e) = result {
+            *LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = Some(Message::from(e.to_string()));
+        }
+    });
+    *RUNNING.lock().unwrap_or_else(|e| e.into_inner()) = Some(Running {
+        port: config.port,
+        token: config.token.clone(),
+        shutdown,
+        handle,
+    });
+    Ok(())
+}
+
+// 有効になっていれば起動する（アプリの起動時）
+pub async fn start_if_enabled() {
+    let config = load_config();
+    if !config.enabled || config.token.is_empty() {
+        return;
+    }
+    let result = start(&config).await.err();
+    *LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = result;
+}
+
+pub fn status() -> ApiServerStatus {
+    let config = load_config();
+    let running_port = RUNNING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|running| running.port);
+    ApiServerStatus {
+        enabled: config.enabled,
+        running: running_port.is_some(),
+        port: config.port,
+        token: config.token,
+        url: format!("http://127.0.0.1:{}/api/v1", running_port.unwrap_or(config.port)),
+        error: LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+    }
+}
+
+// 有効・無効とポートを変更し、サーバーを起動または停止する
+pub async fn configure(enabled: bool, port: Option<u16>) -> Result<ApiServerStatus, Message> {
+    let mut config = load_config();
+    config.enabled = enabled;
+    if let Some(port) = port.filter(|port| *port > 0) {
+        config.port = port;
+    }
+    if config.token.is_empty() {
+        config.token = generate_token()?;
+    }
+    save_config(&config)?;
+
+    *LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    if enabled {
+        if let Err(e) = start(&config).await {
+            *LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = Some(e);
+        }
+    } else {
+        stop().await;
+    }
+    Ok(status())
+}
+
+// トークンを作り直す（以前のトークンは使えなくなる）
+pub async fn regenerate_token() -> Result<ApiServerStatus, Message> {
+    let mut config = load_config();
+    config.token = generate_token()?;
+    save_config(&config)?;
+    if let Some(running) = RUNNING.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        running.token = config.token.clone();
+    }
+    Ok(status())
+}
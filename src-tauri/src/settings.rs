@@ -0,0 +1,174 @@
+use serde_json::{Map, Value};
+use std::fs;
+
+use crate::i18n;
+use crate::i18n::Message;
+use crate::presets;
+use crate::DownloadOptions;
+
+// 設定ファイルが無い場合の設定
+pub fn default_settings() -> Value {
+    serde_json::json!({
+        "outputDirectory": "",
+        "format": "mp4",
+        "quality": "auto",
+        "cookieSource": "none",
+        "cookieFilePath": "",
+        "concurrentConnections": "3",
+        "chapterEmbed": false,
+        "playlistMode": false,
+        "thumbnailEmbed": false,
+        "thumbnailCrop": false,
+        "compatibilityMode": false,
+        "hdrMode": false,
+        "videoCodecPreference": [],
+        "audioCodecPreference": [],
+        "maxFps": null,
+        "minFps": null,
+        "prefer60fps": false,
+        "maxVideoBitrate": null,
+        "maxFilesizeMb": null,
+        "reencodePreset": null,
+        "deleteOriginal": false,
+        "loudnessMode": "none",
+        "loudnessTarget": null,
+        "musicMode": false,
+        "metadataRules": [],
+        "coverCrop": "",
+        "coverSize": null,
+        "coverFormat": "jpg",
+        "saveCoverFile": false,
+        "liveMode": "none",
+        "liveMaxDuration": null,
+        "waitForVideo": false,
+        "waitInterval": 60,
+        "ytDlpPath": "",
+        "ffmpegPath": "",
+        "ffprobePath": "",
+        "collisionPolicy": "skip",
+        "retries": null,
+        "fragmentRetries": null,
+        "maxAttempts": 3,
+        "retryBackoffSeconds": 5,
+        "logRetentionDays": 14,
+        "logMaxTotalMb": 200,
+        "language": "auto",
+        "maxConcurrentDownloads": 2,
+        "clipboardWatch": "off",
+        "clipboardPollIntervalMs": 1000
+    })
+}
+
+// 組み込みのプリセット（項目名はsnake_case）
+pub fn builtin_preset(name: &str) -> Option<Map<String, Value>> {
+    let overrides = match name {
+        "video" => serde_json::json!({ "format": "mp4", "music_mode": false }),
+        "audio" => serde_json::json!({ "format": "mp3", "music_mode": false }),
+        "music" => serde_json::json!({ "format": "mp3", "music_mode": true, "thumbnail_embed": true }),
+        _ => return None,
+    };
+    match overrides {
+        Value::Object(map) => Some(map),
+        _ => None,
+    }
+}
+
+// 保存された設定（保存後に追加された項目はデフォルト値で補う）
+pub fn saved_settings() -> Value {
+    let mut settings = default_settings();
+    let saved = dirs::config_dir()
+        .map(|dir| dir.join("necd-tauri").join("settings.json"))
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok());
+    if let (Some(settings), Some(Value::Object(saved))) = (settings.as_object_mut(), saved) {
+        settings.extend(saved);
+    }
+    settings
+}
+
+// camelCaseの設定をDownloadOptionsの項目名に合わせる（フロントエンドのtoSnakeCaseと同じ規則）
+pub fn to_snake_case(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let mut snake = String::new();
+                    for c in key.chars() {
+                        if c.is_ascii_uppercase() {
+                            snake.push('_');
+                            snake.push(c.to_ascii_lowercase());
+                        } else {
+                            snake.push(c);
+                        }
+                    }
+                    (snake, to_snake_case(value))
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(to_snake_case).collect()),
+        other => other,
+    }
+}
+
+// 保存された設定にプリセット（省略すると既定のプリセット）を重ね、指定された項目（snake_case）で上書きしたオプション
+// （CLI・HTTP API・リンクなど、画面を通さずにダウンロードする場合に共通）
+pub async fn options_for(url: &str, preset: Option<&str>, overrides: &Map<String, Value>) -> Result<DownloadOptions, Message> {
+    let Value::Object(mut options) = to_snake_case(saved_settings()) else {
+        return Err(i18n::Message::new("options.invalid").arg("error", "settings"));
+    };
+    options.extend(presets::resolve(preset)?);
+    options.insert("url".to_string(), Value::String(url.to_string()));
+    for (key, value) in overrides {
+        // 文字列の項目はそのまま文字列として渡す（画質"320"など）
+        let value = match (options.get(key), value) {
+            (Some(Value::String(_)), Value::Number(_) | Value::Bool(_)) => Value::String(value.to_string()),
+            _ => value.clone(),
+        };
+        options.insert(key.clone(), value);
+    }
+
+    let directory = options.get("output_directory").and_then(Value::as_str).unwrap_or_default();
+    if directory.trim().is_empty() {
+        let default_directory = crate::get_default_download_directory().await?;
+        options.insert("output_directory".to_string(), Value::String(default_directory));
+    }
+
+    serde_json::from_value(Value::Object(options)).map_err(|e| i18n::Message::new("options.invalid").arg("error", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 設定ファイル（フロントエンドと同じcamelCase）がDownloadOptionsの項目に届くこと
+    #[test]
+    fn saved_settings_deserialize_into_download_options() {
+        let mut settings = default_settings();
+        settings["url"] = Value::from("https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        settings["outputDirectory"] = Value::from("/tmp/out");
+        settings["prefer60fps"] = Value::from(true);
+        settings["maxFps"] = Value::from(60);
+        settings["maxFilesizeMb"] = Value::from(500);
+
+        let options: DownloadOptions = serde_json::from_value(to_snake_case(settings)).expect("settings should deserialize");
+        assert!(options.prefer_60fps);
+        assert_eq!(options.max_fps, Some(60));
+        assert_eq!(options.max_filesize_mb, Some(500));
+        assert_eq!(options.output_directory, "/tmp/out");
+        assert_eq!(options.format, "mp4");
+    }
+
+    // DownloadOptionsを書き出して読み直しても値が変わらないこと（プリセットの保存）
+    #[test]
+    fn download_options_round_trip() {
+        let mut settings = default_settings();
+        settings["url"] = Value::from("https://youtu.be/dQw4w9WgXcQ");
+        settings["prefer60fps"] = Value::from(true);
+        let options: DownloadOptions = serde_json::from_value(to_snake_case(settings)).expect("settings should deserialize");
+
+        let written = serde_json::to_value(&options).expect("options should serialize");
+        let read: DownloadOptions = serde_json::from_value(written).expect("options should deserialize");
+        assert!(read.prefer_60fps);
+        assert_eq!(read.url, options.url);
+    }
+}
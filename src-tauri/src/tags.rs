@@ -0,0 +1,409 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use tokio::process::Command as TokioCommand;
+
+use crate::i18n::Message;
+use crate::tools;
+
+// タグ編集の対象とする拡張子
+pub const MEDIA_EXTENSIONS: &[&str] = &["mp3", "m4a", "opus", "ogg", "flac", "mp4", "mkv", "webm", "mov"];
+
+// カバー画像を音声ストリームと別に持つ音声形式
+const AUDIO_CONTAINERS: &[&str] = &["mp3", "m4a", "flac"];
+// カバー画像をMETADATA_BLOCK_PICTUREとして持つ形式
+const OGG_CONTAINERS: &[&str] = &["opus", "ogg"];
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct MediaTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub track: Option<String>,
+    pub year: Option<String>,
+    pub genre: Option<String>,
+    pub has_cover: bool,
+}
+
+// タグの変更内容（Noneは変更なし、空文字はタグの削除）
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct TagEdit {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub album: Option<String>,
+    #[serde(default)]
+    pub album_artist: Option<String>,
+    #[serde(default)]
+    pub track: Option<String>,
+    #[serde(default)]
+    pub year: Option<String>,
+    #[serde(default)]
+    pub genre: Option<String>,
+    // 新しいカバー画像のパス
+    #[serde(default)]
+    pub cover_path: Option<String>,
+    #[serde(default)]
+    pub remove_cover: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagWriteResult {
+    pub file: String,
+    pub error: Option<Message>,
+}
+
+// 読み込んだタグ（キーはffmpegの汎用名に正規化）
+struct RawTags {
+    tags: BTreeMap<String, String>,
+    has_cover: bool,
+}
+
+fn extension_of(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+// コンテナごとに異なるタグ名をffmpegの汎用名にそろえる
+fn normalize_key(key: &str) -> String {
+    let key = key.to_lowercase();
+    match key.as_str() {
+        "albumartist" | "album artist" | "tpe2" => "album_artist".to_string(),
+        "tracknumber" | "trck" => "track".to_string(),
+        "year" | "tyer" | "tdrc" => "date".to_string(),
+        _ => key,
+    }
+}
+
+fn ffmpeg_command(program: &str) -> TokioCommand {
+    let mut cmd = tools::command(program);
+    cmd.stdin(std::process::Stdio::null());
+    cmd
+}
+
+async fn read_raw(path: &Path) -> Result<RawTags, Message> {
+    let output = ffmpeg_command(&tools::ffprobe())
+        .args(["-v", "error", "-show_entries", "format_tags:stream=codec_type:stream_tags:stream_disposition=attached_pic", "-of", "json"])
+        .arg(path)
+        .output()
+        .await
+        .map_err(|e| Message::new("command.run_failed").arg("program", "ffprobe").arg("error", e))?;
+    if !output.status.success() {
+        return Err(Message::new("tags.read_failed").arg("error", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let json: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| Message::new("common.json_parse_failed").arg("error", e))?;
+
+    let mut tags = BTreeMap::new();
+    let mut collect = |value: Option<&Value>| {
+        if let Some(map) = value.and_then(|v| v.as_object()) {
+            for (key, value) in map {
+                if let Some(value) = value.as_str() {
+                    tags.entry(normalize_key(key)).or_insert_with(|| value.to_string());
+                }
+            }
+        }
+    };
+    collect(json.pointer("/format/tags"));
+
+    // Opus/Vorbisではタグが音声ストリーム側に付く
+    let mut has_cover = false;
+    let audio_only = AUDIO_CONTAINERS.contains(&extension_of(path).as_str()) || OGG_CONTAINERS.contains(&extension_of(path).as_str());
+    for stream in json.get("streams").and_then(|v| v.as_array()).into_iter().flatten() {
+        let codec_type = stream.get("codec_type").and_then(|v| v.as_str()).unwrap_or_default();
+        let attached = stream.pointer("/disposition/attached_pic").and_then(|v| v.as_i64()) == Some(1);
+        if codec_type == "video" && (attached || audio_only) {
+            has_cover = true;
+        }
+        if codec_type == "audio" {
+            collect(stream.get("tags"));
+        }
+    }
+
+    Ok(RawTags { tags, has_cover })
+}
+
+pub async fn read_tags(path: &Path) -> Result<MediaTags, Message> {
+    let raw = read_raw(path).await?;
+    let get = |key: &str| raw.tags.get(key).cloned();
+    Ok(MediaTags {
+        title: get("title"),
+        artist: get("artist"),
+        album: get("album"),
+        album_artist: get("album_artist"),
+        track: get("track"),
+        year: get("date"),
+        genre: get("genre"),
+        has_cover: raw.has_cover,
+    })
+}
+
+// ffmetadata形式の特殊文字をエスケープ
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn image_mime(data: &[u8]) -> &'static str {
+    if data.starts_with(&[0x89, b'P', b'N', b'G']) {
+        "image/png"
+    } else if data.len() > 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        "image/webp"
+    } else {
+        "image/jpeg"
+    }
+}
+
+// FLACのPICTUREブロックをbase64化したもの（Opus/Vorbisのカバー画像）
+fn metadata_block_picture(data: &[u8]) -> String {
+    let mime = image_mime(data);
+    let mut block = Vec::with_capacity(data.len() + 64);
+    block.extend(3u32.to_be_bytes()); // 表紙
+    block.extend((mime.len() as u32).to_be_bytes());
+    block.extend(mime.as_bytes());
+    block.extend(0u32.to_be_bytes()); // 説明
+    block.extend([0u8; 16]); // 幅・高さ・色深度・パレット数（不明）
+    block.extend((data.len() as u32).to_be_bytes());
+    block.extend(data);
+    STANDARD.encode(block)
+}
+
+// 埋め込み済みのカバー画像を一時ファイルに取り出す
+async fn extract_cover(path: &Path, dir: &Path, stem: &str) -> Option<PathBuf> {
+    let cover = dir.join(format!("{}.necd-cover.img", stem));
+    let status = ffmpeg_command(&tools::ffmpeg())
+        .args(["-hide_banner", "-nostdin", "-y", "-i"])
+        .arg(path)
+        .args(["-map", "0:v:0", "-c", "copy", "-frames:v", "1", "-f", "image2"])
+        .arg(&cover)
+        .output()
+        .await
+        .ok()?
+        .status;
+    if status.success() {
+        Some(cover)
+    } else {
+        let _ = std::fs::remove_file(&cover);
+        None
+    }
+}
+
+fn apply_edit(tags: &mut BTreeMap<String, String>, key: &str, value: &Option<String>) {
+    if let Some(value) = value {
+        if value.trim().is_empty() {
+            tags.remove(key);
+        } else {
+            tags.insert(key.to_string(), value.trim().to_string());
+        }
+    }
+}
+
+// タグ書き込み時のffmpegの引数
+// -mapなどの出力オプションは、すべての入力(-i)を指定した後に並べる
+pub(crate) fn write_args(path: &Path, metadata_path: &Path, temp: &Path, edit: &TagEdit, has_cover: bool) -> Vec<OsString> {
+    let extension = extension_of(path);
+    let is_audio = AUDIO_CONTAINERS.contains(&extension.as_str());
+    let is_ogg = OGG_CONTAINERS.contains(&extension.as_str());
+    let new_cover = edit.cover_path.as_ref().filter(|_| is_audio && !edit.remove_cover);
+
+    let mut args: Vec<OsString> = ["-hide_banner", "-nostdin", "-y", "-i"].iter().map(OsString::from).collect();
+    args.push(path.into());
+    args.extend(["-f", "ffmetadata", "-i"].iter().map(OsString::from));
+    args.push(metadata_path.into());
+    if let Some(cover) = new_cover {
+        args.push("-i".into());
+        args.push(cover.into());
+    }
+
+    let mut push = |values: &[&str]| args.extend(values.iter().map(OsString::from));
+    if is_audio {
+        push(&["-map", "0:a"]);
+        let keeps_cover = if new_cover.is_some() {
+            push(&["-map", "2:v"]);
+            true
+        } else if !edit.remove_cover && has_cover {
+            push(&["-map", "0:v"]);
+            true
+        } else {
+            false
+        };
+        if keeps_cover {
+            push(&["-disposition:v", "attached_pic"]);
+            if extension == "mp3" {
+                push(&["-metadata:s:v", "title=Album cover", "-metadata:s:v", "comment=Cover (front)"]);
+            }
+        }
+        if extension == "mp3" {
+            push(&["-id3v2_version", "3"]);
+        }
+    } else if is_ogg {
+        push(&["-map", "0:a", "-map_metadata:s:a", "1:g"]);
+    } else {
+        push(&["-map", "0"]);
+    }
+    push(&["-c", "copy", "-map_metadata", "1"]);
+    args.push(temp.into());
+    args
+}
+
+pub async fn write_tags(path: &Path, edit: &TagEdit) -> Result<(), Message> {
+    let extension = extension_of(path);
+    if !MEDIA_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(Message::new("tags.unsupported_format").arg("extension", extension));
+    }
+    let is_audio = AUDIO_CONTAINERS.contains(&extension.as_str());
+    let is_ogg = OGG_CONTAINERS.contains(&extension.as_str());
+    let edits_cover = edit.cover_path.is_some() || edit.remove_cover;
+    if edits_cover && !is_audio && !is_ogg {
+        return Err(Message::new("tags.video_cover_unsupported"));
+    }
+
+    let raw = read_raw(path).await?;
+    let mut tags = raw.tags;
+    apply_edit(&mut tags, "title", &edit.title);
+    apply_edit(&mut tags, "artist", &edit.artist);
+    apply_edit(&mut tags, "album", &edit.album);
+    apply_edit(&mut tags, "album_artist", &edit.album_artist);
+    apply_edit(&mut tags, "track", &edit.track);
+    apply_edit(&mut tags, "date", &edit.year);
+    apply_edit(&mut tags, "genre", &edit.genre);
+    // ffmpegが出力時に付け直すタグ
+    tags.remove("encoder");
+
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .ok_or_else(|| Message::new("common.file_name_unavailable"))?;
+    let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let metadata_path = dir.join(format!("{}.necd-meta.txt", stem));
+    let temp = dir.join(format!("{}.necd-tmp.{}", stem, extension));
+
+    // Opus/Vorbisはカバー画像もタグとして書き直す
+    let mut extracted_cover = None;
+    if is_ogg && !edit.remove_cover {
+        let cover = match &edit.cover_path {
+            Some(cover) => Some(PathBuf::from(cover)),
+            None if raw.has_cover => {
+                extracted_cover = extract_cover(path, &dir, &stem).await;
+                extracted_cover.clone()
+            }
+            None => None,
+        };
+        if let Some(cover) = cover {
+            let data = std::fs::read(&cover).map_err(|e| Message::new("cover.read_failed").arg("error", e))?;
+            tags.insert("METADATA_BLOCK_PICTURE".to_string(), metadata_block_picture(&data));
+        }
+    }
+    if let Some(cover) = extracted_cover {
+        let _ = std::fs::remove_file(cover);
+    }
+
+    let mut metadata = String::from(";FFMETADATA1\n");
+    for (key, value) in &tags {
+        metadata.push_str(&format!("{}={}\n", escape_ffmetadata(key), escape_ffmetadata(value)));
+    }
+    std::fs::write(&metadata_path, metadata).map_err(|e| Message::new("tags.temp_failed").arg("error", e))?;
+
+    let mut cmd = ffmpeg_command(&tools::ffmpeg());
+    cmd.args(write_args(path, &metadata_path, &temp, edit, raw.has_cover));
+
+    let output = cmd.output().await.map_err(|e| Message::new("command.run_failed").arg("program", "ffmpeg").arg("error", e))?;
+    let _ = std::fs::remove_file(&metadata_path);
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&temp);
+        return Err(Message::new("tags.write_failed").arg("error", String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or_default()));
+    }
+
+    std::fs::rename(&temp, path).map_err(|e| Message::new("common.replace_failed").arg("error", e))
+}
+
+// 複数ファイルに同じ変更を適用する（プレイリスト単位の一括編集）
+pub async fn write_tags_batch(files: &[String], edit: &TagEdit, auto_track_number: bool) -> Vec<TagWriteResult> {
+    let mut results = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        let mut edit = edit.clone();
+        if auto_track_number {
+            edit.track = Some(format!("{}/{}", index + 1, files.len()));
+        }
+        let error = write_tags(Path::new(file), &edit).await.err();
+        results.push(TagWriteResult { file: file.clone(), error });
+    }
+    results
+}
+
+// フォルダ内のメディアファイルをファイル名順に列挙する
+pub fn list_media_files(directory: &Path) -> Result<Vec<String>, Message> {
+    let entries = std::fs::read_dir(directory).map_err(|e| Message::new("common.read_dir_failed").arg("error", e))?;
+    let mut files: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && MEDIA_EXTENSIONS.contains(&extension_of(path).as_str()))
+        .filter(|path| !path.to_string_lossy().contains(".necd-tmp."))
+        .map(|path| path.display().to_string())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag_args(file: &str, edit: &TagEdit, has_cover: bool) -> Vec<String> {
+        write_args(Path::new(file), Path::new("meta.txt"), Path::new("temp"), edit, has_cover)
+            .iter()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    fn position(args: &[String], value: &str) -> usize {
+        args.iter().position(|arg| arg == value).unwrap()
+    }
+
+    #[test]
+    fn adds_all_inputs_before_output_options() {
+        let edit = TagEdit { cover_path: Some("cover.jpg".to_string()), ..Default::default() };
+        for file in ["song.mp3", "song.m4a", "song.flac"] {
+            let args = tag_args(file, &edit, false);
+            let last_input = args.iter().rposition(|arg| arg == "-i").unwrap();
+            assert_eq!(args[last_input + 1], "cover.jpg");
+            assert!(args.iter().position(|arg| arg == "-map").unwrap() > last_input);
+            assert!(position(&args, "-disposition:v") > last_input);
+            assert_eq!(args[position(&args, "2:v") - 1], "-map");
+        }
+    }
+
+    #[test]
+    fn keeps_existing_cover_without_extra_input() {
+        let args = tag_args("song.m4a", &TagEdit::default(), true);
+        assert_eq!(args.iter().filter(|arg| *arg == "-i").count(), 2);
+        assert!(args.contains(&"0:v".to_string()));
+
+        let edit = TagEdit { remove_cover: true, ..Default::default() };
+        let args = tag_args("song.m4a", &edit, true);
+        assert!(!args.contains(&"0:v".to_string()));
+        assert!(!args.contains(&"-disposition:v".to_string()));
+    }
+
+    #[test]
+    fn ogg_cover_is_written_as_tag() {
+        let edit = TagEdit { cover_path: Some("cover.jpg".to_string()), ..Default::default() };
+        let args = tag_args("song.opus", &edit, false);
+        assert_eq!(args.iter().filter(|arg| *arg == "-i").count(), 2);
+        assert!(!args.contains(&"cover.jpg".to_string()));
+        assert_eq!(args.last().unwrap(), "temp");
+    }
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use tokio::process::Command as TokioCommand;
+
+use crate::i18n::Message;
+use crate::ytdlp;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ToolPath {
+    pub name: String,
+    // 設定で指定されたパス
+    pub configured: Option<String>,
+    // 実際に使うパス（見つからなければコマンド名のまま）
+    pub resolved: String,
+    pub found: bool,
+    // 設定で指定されたパスが存在しない場合の説明（その場合は検出したものを使う）
+    pub error: Option<Message>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ToolPaths {
+    pub yt_dlp: ToolPath,
+    pub ffmpeg: ToolPath,
+    pub ffprobe: ToolPath,
+}
+
+fn executable_name(name: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    }
+}
+
+// 設定ファイルに保存されたパス（ytDlpPath, ffmpegPath, ffprobePath）
+fn configured_path(key: &str) -> Option<String> {
+    let settings_path = dirs::config_dir()?.join("necd-tauri").join("settings.json");
+    let content = std::fs::read_to_string(settings_path).ok()?;
+    let settings: Value = serde_json::from_str(&content).ok()?;
+    settings
+        .get(key)
+        .and_then(|value| value.as_str())
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+}
+
+// PATH以外でよく使われるインストール先
+fn common_locations() -> Vec<PathBuf> {
+    let mut dirs_list = Vec::new();
+    let home = dirs::home_dir();
+
+    if let Ok(pyenv_root) = env::var("PYENV_ROOT") {
+        dirs_list.push(PathBuf::from(pyenv_root).join("shims"));
+    }
+    if let Some(home) = &home {
+        dirs_list.push(home.join(".pyenv").join("shims"));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        dirs_list.push(PathBuf::from("/opt/homebrew/bin"));
+        dirs_list.push(PathBuf::from("/usr/local/bin"));
+        dirs_list.push(PathBuf::from("/opt/local/bin"));
+        if let Some(home) = &home {
+            dirs_list.push(home.join(".local").join("bin"));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        dirs_list.push(PathBuf::from("/usr/local/bin"));
+        dirs_list.push(PathBuf::from("/usr/bin"));
+        dirs_list.push(PathBuf::from("/snap/bin"));
+        dirs_list.push(PathBuf::from("/var/lib/flatpak/exports/bin"));
+        if let Some(home) = &home {
+            dirs_list.push(home.join(".local").join("bin"));
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Ok(local) = env::var("LOCALAPPDATA") {
+            let local = PathBuf::from(local);
+            dirs_list.push(local.join("Microsoft").join("WinGet").join("Links"));
+            dirs_list.push(local.join("Programs").join("Python").join("Scripts"));
+        }
+        if let Ok(appdata) = env::var("APPDATA") {
+            dirs_list.push(PathBuf::from(appdata).join("Python").join("Scripts"));
+        }
+        if let Some(home) = &home {
+            dirs_list.push(home.join("scoop").join("shims"));
+        }
+        dirs_list.push(PathBuf::from(r"C:\ProgramData\chocolatey\bin"));
+        dirs_list.push(PathBuf::from(r"C:\ffmpeg\bin"));
+        dirs_list.push(PathBuf::from(r"C:\Program Files\ffmpeg\bin"));
+    }
+
+    dirs_list
+}
+
+// PATHと一般的なインストール先からコマンドを探す
+pub fn discover(name: &str) -> Option<PathBuf> {
+    let executable = executable_name(name);
+    let path_dirs = env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default();
+    path_dirs
+        .into_iter()
+        .chain(common_locations())
+        .map(|dir| dir.join(&executable))
+        .find(|path| path.is_file())
+}
+
+// 子プロセスに渡すPATH（一般的なインストール先を末尾に追加）
+pub fn search_path() -> Option<OsString> {
+    let mut paths: Vec<PathBuf> = env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).collect())
+        .unwrap_or_default();
+    for dir in common_locations() {
+        if dir.is_dir() && !paths.contains(&dir) {
+            paths.push(dir);
+        }
+    }
+    env::join_paths(paths).ok()
+}
+
+// yt-dlp・ffmpegなどの子プロセス（どのコマンドからも同じPATHでffmpegやPythonを探せるようにする）
+pub fn command(program: impl AsRef<OsStr>) -> TokioCommand {
+    let mut cmd = TokioCommand::new(program);
+    if let Some(path) = search_path() {
+        cmd.env("PATH", path);
+    }
+    #[cfg(target_os = "windows")]
+    {
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    cmd
+}
+
+fn resolve(name: &str, setting_key: &str) -> ToolPath {
+    let configured = configured_path(setting_key);
+    let explicit = configured.as_ref().filter(|path| Path::new(path).is_file()).cloned();
+    let error = configured
+        .as_ref()
+        .filter(|_| explicit.is_none())
+        .map(|path| Message::new("tools.configured_missing").arg("name", name).arg("path", path));
+    // yt-dlpは設定での指定が無ければアプリが管理しているものを使う
+    let managed = if name == "yt-dlp" { ytdlp::active_path() } else { None };
+    let resolved = explicit
+        .or(managed)
+        .or_else(|| discover(name).map(|path| path.to_string_lossy().to_string()));
+    ToolPath {
+        name: name.to_string(),
+        configured,
+        found: resolved.is_some(),
+        resolved: resolved.unwrap_or_else(|| name.to_string()),
+        error,
+    }
+}
+
+pub fn yt_dlp() -> String {
+    resolve("yt-dlp", "ytDlpPath").resolved
+}
+
+pub fn ffmpeg() -> String {
+    resolve("ffmpeg", "ffmpegPath").resolved
+}
+
+pub fn ffprobe() -> String {
+    resolve("ffprobe", "ffprobePath").resolved
+}
+
+// yt-dlpの--ffmpeg-locationに渡すパス（見つからなければyt-dlp自身の検索に任せる）
+pub fn ffmpeg_location() -> Option<String> {
+    let ffmpeg = resolve("ffmpeg", "ffmpegPath");
+    if ffmpeg.found {
+        Some(ffmpeg.resolved)
+    } else {
+        None
+    }
+}
+
+pub fn all() -> ToolPaths {
+    ToolPaths {
+        yt_dlp: resolve("yt-dlp", "ytDlpPath"),
+        ffmpeg: resolve("ffmpeg", "ffmpegPath"),
+        ffprobe: resolve("ffprobe", "ffprobePath"),
+    }
+}
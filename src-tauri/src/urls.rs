@@ -0,0 +1,510 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::i18n::Message;
+
+// URLの種類
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlKind {
+    Video,
+    Short,
+    Playlist,
+    Channel,
+    Live,
+    // YouTube Musicの曲
+    Music,
+    // YouTube以外、または判定できないURL
+    Other,
+}
+
+// 判定結果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClassifiedUrl {
+    pub original: String,
+    // トラッキング用のパラメータを取り除いたURL（動画とプレイリストの両方を含む場合は両方を残す）
+    pub normalized: String,
+    pub kind: UrlKind,
+    // music.youtube.comのURL
+    pub music: bool,
+    pub video_id: Option<String>,
+    pub playlist_id: Option<String>,
+    // "RD"で始まる自動生成のミックス（終わりが無い）
+    pub mix: bool,
+    // @ハンドル、チャンネルIDなど
+    pub channel: Option<String>,
+    // t=の再生開始位置（秒）
+    pub start_seconds: Option<u64>,
+    // 動画とプレイリストのどちらも指している（どちらをダウンロードするか選んでもらう）
+    pub ambiguous: bool,
+    // 曖昧な場合の選択肢
+    pub video_url: Option<String>,
+    pub playlist_url: Option<String>,
+    // 取り除いたパラメータ名
+    pub removed_params: Vec<String>,
+}
+
+const YOUTUBE_HOSTS: &[&str] = &["youtube.com", "www.youtube.com", "m.youtube.com", "youtube-nocookie.com", "www.youtube-nocookie.com"];
+const MUSIC_HOST: &str = "music.youtube.com";
+const SHORT_HOST: &str = "youtu.be";
+
+// 対応するサイト（サブドメインを含む）
+const MEDIA_HOSTS: &[&str] = &[
+    "youtube.com",
+    "youtu.be",
+    "youtube-nocookie.com",
+    "nicovideo.jp",
+    "nico.ms",
+    "twitch.tv",
+    "vimeo.com",
+    "x.com",
+    "twitter.com",
+    "soundcloud.com",
+    "bandcamp.com",
+    "bilibili.com",
+    "tiktok.com",
+    "instagram.com",
+    "dailymotion.com",
+];
+
+// どのサイトでも取り除くパラメータ
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "dclid", "msclkid", "igshid", "mc_cid", "mc_eid", "ref_src", "ref_url"];
+// YouTubeで動画・プレイリストの指定以外のパラメータ（v, list, tは残す）
+const YOUTUBE_KEEP_PARAMS: &[&str] = &["v", "list", "t"];
+
+fn is_tracking_param(name: &str) -> bool {
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name)
+}
+
+fn valid_video_id(id: &str) -> bool {
+    id.len() == 11 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn valid_playlist_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// "90", "90s", "1m30s", "1h2m3s" を秒にする
+fn parse_start(value: &str) -> Option<u64> {
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(seconds);
+    }
+    let mut total = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let amount: u64 = number.parse().ok()?;
+        number.clear();
+        total += match c {
+            'h' => amount * 3600,
+            'm' => amount * 60,
+            's' => amount,
+            _ => return None,
+        };
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    Some(total)
+}
+
+fn watch_url(host: &str, video_id: &str, playlist_id: Option<&str>, start: Option<u64>) -> String {
+    let mut url = format!("https://{}/watch?v={}", host, video_id);
+    if let Some(list) = playlist_id {
+        url.push_str(&format!("&list={}", list));
+    }
+    if let Some(start) = start.filter(|start| *start > 0) {
+        url.push_str(&format!("&t={}s", start));
+    }
+    url
+}
+
+fn playlist_url(host: &str, playlist_id: &str) -> String {
+    format!("https://{}/playlist?list={}", host, playlist_id)
+}
+
+struct YouTubeParts {
+    video_id: Option<String>,
+    playlist_id: Option<String>,
+    start: Option<u64>,
+    short: bool,
+    live: bool,
+    channel: Option<String>,
+    // チャンネルのURLの正規化後のパス（/@handle/videos など）
+    channel_path: Option<String>,
+}
+
+fn youtube_parts(url: &Url, host: &str) -> Result<YouTubeParts, Message> {
+    let query = |name: &str| url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.trim().to_string());
+    let segments: Vec<&str> = url.path_segments().map(|segments| segments.filter(|s| !s.is_empty()).collect()).unwrap_or_default();
+
+    let mut parts = YouTubeParts {
+        video_id: query("v"),
+        playlist_id: query("list"),
+        start: query("t").or_else(|| query("start")).as_deref().and_then(parse_start),
+        short: false,
+        live: false,
+        channel: None,
+        channel_path: None,
+    };
+
+    if host == SHORT_HOST {
+        parts.video_id = segments.first().map(|id| id.to_string());
+    } else {
+        match segments.as_slice() {
+            ["shorts", id, ..] => {
+                parts.video_id = Some(id.to_string());
+                parts.short = true;
+            }
+            ["live", id, ..] => {
+                parts.video_id = Some(id.to_string());
+                parts.live = true;
+            }
+            ["embed", "videoseries", ..] => {}
+            ["embed", id, ..] | ["v", id, ..] | ["e", id, ..] => parts.video_id = Some(id.to_string()),
+            ["watch", ..] | ["playlist", ..] | [] => {}
+            [handle, rest @ ..] if handle.starts_with('@') => {
+                parts.channel = Some(handle.to_string());
+                parts.live = rest.first() == Some(&"live");
+                parts.channel_path = Some(format!("/{}", segments.join("/")));
+            }
+            ["channel" | "c" | "user", name, rest @ ..] => {
+                parts.channel = Some(name.to_string());
+                parts.live = rest.first() == Some(&"live");
+                parts.channel_path = Some(format!("/{}", segments.join("/")));
+            }
+            _ => return Err(Message::new("url.unsupported_youtube").arg("url", url.as_str())),
+        }
+    }
+
+    if let Some(id) = &parts.video_id {
+        if !valid_video_id(id) {
+            return Err(Message::new("url.invalid_video_id").arg("id", id));
+        }
+    }
+    if let Some(id) = &parts.playlist_id {
+        if !valid_playlist_id(id) {
+            return Err(Message::new("url.invalid_playlist_id").arg("id", id));
+        }
+    }
+    Ok(parts)
+}
+
+fn classify_youtube(original: &str, url: &Url, host: &str) -> Result<ClassifiedUrl, Message> {
+    let music = host == MUSIC_HOST;
+    let parts = youtube_parts(url, host)?;
+    let canonical_host = if music { MUSIC_HOST } else { "www.youtube.com" };
+    let removed_params = url
+        .query_pairs()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !YOUTUBE_KEEP_PARAMS.contains(&key.as_str()))
+        .collect();
+
+    let mut result = ClassifiedUrl {
+        original: original.to_string(),
+        normalized: String::new(),
+        kind: UrlKind::Other,
+        music,
+        video_id: parts.video_id.clone(),
+        playlist_id: parts.playlist_id.clone(),
+        mix: parts.playlist_id.as_deref().is_some_and(|id| id.starts_with("RD")),
+        channel: parts.channel.clone(),
+        start_seconds: parts.start,
+        ambiguous: false,
+        video_url: None,
+        playlist_url: None,
+        removed_params,
+    };
+
+    match (&parts.video_id, &parts.playlist_id, &parts.channel_path) {
+        (Some(video_id), playlist_id, _) => {
+            result.kind = if parts.short {
+                UrlKind::Short
+            } else if parts.live {
+                UrlKind::Live
+            } else if music {
+                UrlKind::Music
+            } else {
+                UrlKind::Video
+            };
+            let video_url = if parts.short {
+                format!("https://www.youtube.com/shorts/{}", video_id)
+            } else {
+                watch_url(canonical_host, video_id, None, parts.start)
+            };
+            match playlist_id {
+                Some(list) => {
+                    result.ambiguous = true;
+                    result.normalized = watch_url(canonical_host, video_id, Some(list), parts.start);
+                    result.video_url = Some(video_url);
+                    result.playlist_url = Some(playlist_url(canonical_host, list));
+                }
+                None => result.normalized = video_url,
+            }
+        }
+        (None, Some(list), _) => {
+            result.kind = UrlKind::Playlist;
+            result.normalized = playlist_url(canonical_host, list);
+        }
+        (None, None, Some(path)) => {
+            result.kind = if parts.live { UrlKind::Live } else { UrlKind::Channel };
+            result.normalized = format!("https://{}{}", canonical_host, path);
+        }
+        (None, None, None) => return Err(Message::new("url.unsupported_youtube").arg("url", original)),
+    }
+    Ok(result)
+}
+
+// URLの種類を判定し、トラッキング用のパラメータを取り除く
+pub fn classify(text: &str) -> Result<ClassifiedUrl, Message> {
+    let original = text.trim();
+    let url = Url::parse(original).map_err(|_| Message::new("url.invalid").arg("url", original))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Message::new("url.invalid").arg("url", original));
+    }
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+
+    if host == SHORT_HOST || host == MUSIC_HOST || YOUTUBE_HOSTS.contains(&host.as_str()) {
+        return classify_youtube(original, &url, &host);
+    }
+
+    // YouTube以外はトラッキング用のパラメータのみ取り除く
+    let mut cleaned = url.clone();
+    let pairs: Vec<(String, String)> = url.query_pairs().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+    let removed: Vec<String> = pairs.iter().filter(|(key, _)| is_tracking_param(key)).map(|(key, _)| key.clone()).collect();
+    if !removed.is_empty() {
+        let kept: Vec<&(String, String)> = pairs.iter().filter(|(key, _)| !is_tracking_param(key)).collect();
+        if kept.is_empty() {
+            cleaned.set_query(None);
+        } else {
+            cleaned.query_pairs_mut().clear().extend_pairs(kept);
+        }
+    }
+    Ok(ClassifiedUrl {
+        original: original.to_string(),
+        normalized: cleaned.to_string(),
+        kind: UrlKind::Other,
+        music: false,
+        video_id: None,
+        playlist_id: None,
+        mix: false,
+        channel: None,
+        start_seconds: None,
+        ambiguous: false,
+        video_url: None,
+        playlist_url: None,
+        removed_params: removed,
+    })
+}
+
+// yt-dlpに渡すURL（動画とプレイリストの両方を指す場合はplaylist_modeで選ぶ）
+pub fn resolve(text: &str, playlist_mode: bool) -> String {
+    match classify(text) {
+        Ok(classified) if classified.ambiguous => {
+            let chosen = if playlist_mode { classified.playlist_url } else { classified.video_url };
+            chosen.unwrap_or(classified.normalized)
+        }
+        Ok(classified) => classified.normalized,
+        // 判定できないURLはそのままyt-dlpに任せる
+        Err(_) => text.trim().to_string(),
+    }
+}
+
+// ホストのあるhttp(s)のURL（yt-dlpのオプションやローカルのパスとして解釈されないもの）
+pub fn is_web_url(text: &str) -> bool {
+    Url::parse(text.trim())
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host_str().is_some_and(|host| !host.is_empty()))
+}
+
+// 対応サイトのURL
+pub fn is_media_url(url: &Url) -> bool {
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.to_lowercase();
+    MEDIA_HOSTS
+        .iter()
+        .any(|media| host == *media || host.ends_with(&format!(".{}", media)))
+}
+
+// 文章やMarkdownのリンクの中からhttp(s)のURLを取り出す（前後の括弧や句読点は含めない）
+// 日本語の文章ではURLの直後に空白を入れずに続けることが多いため、全角の句読点や括弧でも区切る
+pub fn find_urls(text: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("http") {
+        let candidate = &rest[start..];
+        let end = candidate
+            .find(|c: char| {
+                c.is_whitespace()
+                    || matches!(c, '<' | '>' | '"' | '\'' | '(' | ')' | '[' | ']' | '|' | '`')
+                    || matches!(c, '「' | '」' | '『' | '』' | '（' | '）' | '【' | '】' | '、' | '。' | '！' | '？')
+            })
+            .unwrap_or(candidate.len());
+        let url = candidate[..end].trim_end_matches(['.', ',', '!', '?', ';', ':']);
+        if url.starts_with("http://") || url.starts_with("https://") {
+            found.push(url);
+        }
+        rest = &candidate[end.max(4)..];
+    }
+    found
+}
+
+// 対応サイトのURLを正規化して重複を除いたもの（共有用のsi=などが違うだけの同じURLは1つにする）
+pub fn extract_media_urls(text: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for candidate in find_urls(text) {
+        let Some(url) = Url::parse(candidate).ok().filter(is_media_url) else {
+            continue;
+        };
+        let url = classify(url.as_str()).map(|classified| classified.normalized).unwrap_or_else(|_| url.to_string());
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classified(url: &str) -> ClassifiedUrl {
+        classify(url).unwrap()
+    }
+
+    #[test]
+    fn classifies_watch_url() {
+        let result = classified("https://www.youtube.com/watch?v=dQw4w9WgXcQ&si=abc&feature=share&t=1m30s");
+        assert_eq!(result.kind, UrlKind::Video);
+        assert_eq!(result.video_id.as_deref(), Some("dQw4w9WgXcQ"));
+        assert_eq!(result.start_seconds, Some(90));
+        assert_eq!(result.normalized, "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=90s");
+        assert_eq!(result.removed_params, vec!["si", "feature"]);
+        assert!(!result.ambiguous);
+    }
+
+    #[test]
+    fn classifies_short_url() {
+        let result = classified("https://m.youtube.com/shorts/dQw4w9WgXcQ?feature=share");
+        assert_eq!(result.kind, UrlKind::Short);
+        assert_eq!(result.normalized, "https://www.youtube.com/shorts/dQw4w9WgXcQ");
+    }
+
+    #[test]
+    fn classifies_live_urls() {
+        let video = classified("https://www.youtube.com/live/dQw4w9WgXcQ?si=abc");
+        assert_eq!(video.kind, UrlKind::Live);
+        assert_eq!(video.normalized, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+
+        let channel = classified("https://www.youtube.com/@example/live");
+        assert_eq!(channel.kind, UrlKind::Live);
+        assert_eq!(channel.channel.as_deref(), Some("@example"));
+        assert_eq!(channel.normalized, "https://www.youtube.com/@example/live");
+    }
+
+    #[test]
+    fn classifies_youtu_be_url() {
+        let result = classified("https://youtu.be/dQw4w9WgXcQ?si=abc&t=42");
+        assert_eq!(result.kind, UrlKind::Video);
+        assert_eq!(result.normalized, "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42s");
+    }
+
+    #[test]
+    fn classifies_music_url() {
+        let result = classified("https://music.youtube.com/watch?v=dQw4w9WgXcQ&feature=share");
+        assert_eq!(result.kind, UrlKind::Music);
+        assert!(result.music);
+        assert_eq!(result.normalized, "https://music.youtube.com/watch?v=dQw4w9WgXcQ");
+    }
+
+    #[test]
+    fn classifies_playlist_and_channel() {
+        let playlist = classified("https://www.youtube.com/playlist?list=PLabcdef123&si=abc");
+        assert_eq!(playlist.kind, UrlKind::Playlist);
+        assert_eq!(playlist.normalized, "https://www.youtube.com/playlist?list=PLabcdef123");
+
+        let channel = classified("https://www.youtube.com/channel/UCabcdef123/videos");
+        assert_eq!(channel.kind, UrlKind::Channel);
+        assert_eq!(channel.normalized, "https://www.youtube.com/channel/UCabcdef123/videos");
+    }
+
+    #[test]
+    fn video_in_playlist_is_ambiguous() {
+        let url = "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PLabcdef123&index=3";
+        let result = classified(url);
+        assert!(result.ambiguous);
+        assert!(!result.mix);
+        assert_eq!(result.normalized, "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PLabcdef123");
+        assert_eq!(result.video_url.as_deref(), Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ"));
+        assert_eq!(result.playlist_url.as_deref(), Some("https://www.youtube.com/playlist?list=PLabcdef123"));
+        assert_eq!(resolve(url, false), "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert_eq!(resolve(url, true), "https://www.youtube.com/playlist?list=PLabcdef123");
+    }
+
+    #[test]
+    fn detects_mix_playlist() {
+        let result = classified("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=RDdQw4w9WgXcQ&start_radio=1");
+        assert!(result.mix);
+        assert!(result.ambiguous);
+        assert_eq!(result.removed_params, vec!["start_radio"]);
+    }
+
+    #[test]
+    fn rejects_invalid_ids_and_keeps_them_for_yt_dlp() {
+        assert!(classify("https://www.youtube.com/watch?v=short").is_err());
+        assert!(classify("ftp://www.youtube.com/watch?v=dQw4w9WgXcQ").is_err());
+        assert_eq!(resolve(" https://www.youtube.com/watch?v=short ", false), "https://www.youtube.com/watch?v=short");
+    }
+
+    #[test]
+    fn removes_tracking_params_from_other_sites() {
+        let result = classified("https://vimeo.com/123456?utm_source=x&fbclid=y&h=abc");
+        assert_eq!(result.kind, UrlKind::Other);
+        assert_eq!(result.normalized, "https://vimeo.com/123456?h=abc");
+        assert_eq!(result.removed_params, vec!["utm_source", "fbclid"]);
+    }
+
+    #[test]
+    fn finds_urls_without_trailing_punctuation() {
+        let text = "見て→https://youtu.be/dQw4w9WgXcQ。あと https://example.com/a, https://example.com/b! それと「https://example.com/c」";
+        assert_eq!(
+            find_urls(text),
+            vec!["https://youtu.be/dQw4w9WgXcQ", "https://example.com/a", "https://example.com/b", "https://example.com/c"]
+        );
+    }
+
+    #[test]
+    fn finds_urls_inside_parentheses_and_markdown() {
+        let text = "(https://example.com/a) [曲](https://music.youtube.com/watch?v=dQw4w9WgXcQ) <https://example.com/b>.";
+        assert_eq!(
+            find_urls(text),
+            vec!["https://example.com/a", "https://music.youtube.com/watch?v=dQw4w9WgXcQ", "https://example.com/b"]
+        );
+    }
+
+    #[test]
+    fn extracts_only_media_urls_without_duplicates() {
+        let text = "https://youtu.be/dQw4w9WgXcQ?si=a https://www.youtube.com/watch?v=dQw4w9WgXcQ&si=b https://blog.example.com/post (https://nicovideo.jp/watch/sm9)";
+        assert_eq!(
+            extract_media_urls(text),
+            vec!["https://www.youtube.com/watch?v=dQw4w9WgXcQ", "https://nicovideo.jp/watch/sm9"]
+        );
+    }
+
+    #[test]
+    fn accepts_only_http_urls_as_web_urls() {
+        assert!(is_web_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ"));
+        assert!(is_web_url(" http://example.com/video "));
+        assert!(!is_web_url("--exec=calc.exe"));
+        assert!(!is_web_url("file:///etc/passwd"));
+        assert!(!is_web_url("ytsearch:never gonna give you up"));
+        assert!(!is_web_url("example.com/video"));
+    }
+}
@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::i18n::Message;
+use crate::tools;
+
+// 公式のリリースページ（ミラーを使う場合は同じ構成のURLを設定する）
+const DEFAULT_RELEASE_URL: &str = "https://github.com/yt-dlp/yt-dlp/releases";
+// リリースに含まれるチェックサムファイル
+const CHECKSUM_FILE: &str = "SHA2-256SUMS";
+// ロールバック用に残すバージョン数
+const KEEP_VERSIONS: usize = 3;
+
+// 管理しているyt-dlpの状態（state.jsonに保存）
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct ManagedState {
+    #[serde(default)]
+    active_version: Option<String>,
+    #[serde(default)]
+    pinned_version: Option<String>,
+    #[serde(default)]
+    release_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManagedInfo {
+    pub active_version: Option<String>,
+    pub pinned_version: Option<String>,
+    pub release_url: String,
+    pub installed_versions: Vec<String>,
+    pub path: Option<String>,
+}
+
+fn managed_dir() -> Result<PathBuf, Message> {
+    Ok(dirs::data_dir()
+        .ok_or_else(|| Message::new("common.data_dir_unavailable"))?
+        .join("necd-tauri")
+        .join("yt-dlp"))
+}
+
+fn versions_dir() -> Result<PathBuf, Message> {
+    Ok(managed_dir()?.join("versions"))
+}
+
+fn load_state() -> ManagedState {
+    managed_dir()
+        .ok()
+        .and_then(|dir| fs::read_to_string(dir.join("state.json")).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &ManagedState) -> Result<(), Message> {
+    let dir = managed_dir()?;
+    fs::create_dir_all(&dir).map_err(|e| Message::new("common.dir_create_failed").arg("error", e))?;
+    let json = serde_json::to_string_pretty(state)
+        .map_err(|e| Message::new("ytdlp.state_serialize_failed").arg("error", e))?;
+    fs::write(dir.join("state.json"), json).map_err(|e| Message::new("ytdlp.state_save_failed").arg("error", e))
+}
+
+fn release_url(state: &ManagedState) -> String {
+    state
+        .release_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .unwrap_or(DEFAULT_RELEASE_URL)
+        .trim_end_matches('/')
+        .to_string()
+}
+
+// OSごとの単体実行ファイル（Python不要）のアセット名
+fn asset_name() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "yt-dlp.exe"
+    } else if cfg!(target_os = "macos") {
+        "yt-dlp_macos"
+    } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+        "yt-dlp_linux_aarch64"
+    } else if cfg!(target_os = "linux") {
+        "yt-dlp_linux"
+    } else {
+        "yt-dlp"
+    }
+}
+
+fn binary_name() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "yt-dlp.exe"
+    } else {
+        "yt-dlp"
+    }
+}
+
+fn binary_path(version: &str) -> Result<PathBuf, Message> {
+    Ok(versions_dir()?.join(version).join(binary_name()))
+}
+
+// インストール済みのバージョン（古い順、作業用の.stagingなどは除く）
+fn installed_versions() -> Vec<String> {
+    let mut versions: Vec<String> = versions_dir()
+        .ok()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+                .filter(|entry| entry.path().join(binary_name()).exists())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    // バージョンは YYYY.MM.DD 形式なので文字列順で比較できる
+    versions.sort();
+    versions
+}
+
+// 有効な管理対象のyt-dlp（無ければNone）
+pub fn active_path() -> Option<String> {
+    let version = load_state().active_version?;
+    let path = binary_path(&version).ok()?;
+    if path.exists() {
+        Some(path.to_string_lossy().to_string())
+    } else {
+        None
+    }
+}
+
+pub fn info() -> ManagedInfo {
+    let state = load_state();
+    ManagedInfo {
+        release_url: release_url(&state),
+        installed_versions: installed_versions(),
+        path: active_path(),
+        active_version: state.active_version,
+        pinned_version: state.pinned_version,
+    }
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> Result<Vec<u8>, Message> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| Message::new("ytdlp.fetch_failed").arg("url", url).arg("error", e))?;
+    if !response.status().is_success() {
+        return Err(Message::new("ytdlp.fetch_failed").arg("url", url).arg("error", format!("HTTP {}", response.status().as_u16())));
+    }
+    response
+        .bytes()
+        .await
+        .map(|bytes| bytes.to_vec())
+        .map_err(|e| Message::new("ytdlp.fetch_failed").arg("url", url).arg("error", e))
+}
+
+// SHA2-256SUMSから指定したアセットのハッシュを探す
+fn expected_checksum(sums: &str, asset: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let hash = parts.next()?;
+        let name = parts.next()?.trim_start_matches('*');
+        (name == asset).then(|| hash.to_lowercase())
+    })
+}
+
+async fn binary_version(path: &Path) -> Result<String, Message> {
+    let mut cmd = tools::command(path);
+    cmd.arg("--version");
+    let output = cmd.output().await.map_err(|e| Message::new("command.run_failed").arg("program", "yt-dlp").arg("error", e))?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !version.is_empty() {
+        Ok(version)
+    } else {
+        Err(Message::new("ytdlp.version_check_failed"))
+    }
+}
+
+// バージョンを固定している場合は、それ以外のバージョンへの更新や切り替えを断る
+fn check_pin(state: &ManagedState, version: &str) -> Result<(), Message> {
+    match state.pinned_version.as_deref() {
+        Some(pinned) if pinned != version => {
+            Err(Message::new("ytdlp.pinned").arg("pinned", pinned).arg("version", version))
+        }
+        _ => Ok(()),
+    }
+}
+
+// 有効なバージョンと固定したバージョン以外の古いものを削除する
+fn prune_versions(state: &ManagedState) {
+    let versions = installed_versions();
+    if versions.len() <= KEEP_VERSIONS {
+        return;
+    }
+    let Ok(dir) = versions_dir() else {
+        return;
+    };
+    let removable = versions.len() - KEEP_VERSIONS;
+    for version in versions
+        .iter()
+        .filter(|version| state.active_version.as_ref() != Some(*version) && state.pinned_version.as_ref() != Some(*version))
+        .take(removable)
+    {
+        let _ = fs::remove_dir_all(dir.join(version));
+    }
+}
+
+// リリースからyt-dlpを取得し、チェックサムを検証してから有効にする
+// versionを省略した場合は固定したバージョン、それも無ければ最新版
+pub async fn install(version: Option<String>) -> Result<String, Message> {
+    let mut state = load_state();
+    let version = version
+        .map(|version| version.trim().to_string())
+        .filter(|version| !version.is_empty())
+        .or_else(|| state.pinned_version.clone());
+    if let Some(version) = &version {
+        check_pin(&state, version)?;
+    }
+    let base = release_url(&state);
+    let release = match &version {
+        Some(version) => format!("{}/download/{}", base, version),
+        None => format!("{}/latest/download", base),
+    };
+
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("necd-tauri/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| Message::new("ytdlp.http_client_failed").arg("error", e))?;
+
+    let asset = asset_name();
+    let sums = fetch(&client, &format!("{}/{}", release, CHECKSUM_FILE)).await?;
+    let expected = expected_checksum(&String::from_utf8_lossy(&sums), asset)
+        .ok_or_else(|| Message::new("ytdlp.checksum_missing").arg("asset", asset))?;
+    let binary = fetch(&client, &format!("{}/{}", release, asset)).await?;
+    let actual = format!("{:x}", Sha256::digest(&binary));
+    if actual != expected {
+        return Err(Message::new("ytdlp.checksum_mismatch").arg("expected", expected).arg("actual", actual));
+    }
+
+    // 一時ディレクトリで実行できることを確認してからバージョンのディレクトリへ移す
+    let versions = versions_dir()?;
+    let staging = versions.join(".staging");
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(&staging).map_err(|e| Message::new("common.dir_create_failed").arg("error", e))?;
+    let staged = staging.join(binary_name());
+    fs::write(&staged, &binary).map_err(|e| Message::new("ytdlp.save_failed").arg("error", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))
+            .map_err(|e| Message::new("ytdlp.permission_failed").arg("error", e))?;
+    }
+    // ミラーが別のバージョンを返した場合も固定を守る
+    let installed_version = match binary_version(&staged).await.and_then(|version| check_pin(&state, &version).map(|_| version)) {
+        Ok(version) => version,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+    };
+
+    let target = versions.join(&installed_version);
+    let _ = fs::remove_dir_all(&target);
+    fs::rename(&staging, &target).map_err(|e| Message::new("ytdlp.place_failed").arg("error", e))?;
+
+    state.active_version = Some(installed_version.clone());
+    save_state(&state)?;
+    prune_versions(&state);
+    Ok(installed_version)
+}
+
+// インストール済みのバージョンに切り替える
+pub fn activate(version: &str) -> Result<(), Message> {
+    if !binary_path(version)?.exists() {
+        return Err(Message::new("ytdlp.not_installed").arg("version", version));
+    }
+    let mut state = load_state();
+    check_pin(&state, version)?;
+    state.active_version = Some(version.to_string());
+    save_state(&state)
+}
+
+// 有効なバージョンの一つ前に戻す
+pub fn rollback() -> Result<String, Message> {
+    let state = load_state();
+    let versions = installed_versions();
+    let previous = match state.active_version.as_ref().and_then(|active| versions.iter().position(|v| v == active)) {
+        Some(index) if index > 0 => versions[index - 1].clone(),
+        _ => return Err(Message::new("ytdlp.no_previous")),
+    };
+    activate(&previous)?;
+    Ok(previous)
+}
+
+// バージョンを固定する（Noneで解除）
+// 固定したバージョンがインストール済みであれば有効にする（未インストールの場合はinstallで取得する）
+pub fn set_pin(version: Option<String>) -> Result<(), Message> {
+    let mut state = load_state();
+    state.pinned_version = version.filter(|version| !version.trim().is_empty()).map(|version| version.trim().to_string());
+    if let Some(pinned) = &state.pinned_version {
+        if binary_path(pinned)?.exists() {
+            state.active_version = Some(pinned.clone());
+        }
+    }
+    save_state(&state)
+}
+
+// リリースのURLを設定する（Noneで公式に戻す）
+pub fn set_release_url(url: Option<String>) -> Result<(), Message> {
+    let mut state = load_state();
+    state.release_url = url.filter(|url| !url.trim().is_empty());
+    save_state(&state)
+}
@@ -1,4 +1,4 @@
-import { invoke } from '@tauri-apps/api/core'
+import { invoke as tauriInvoke } from '@tauri-apps/api/core'
 
 export type DownloadOptions = {
   url: string
@@ -14,18 +14,335 @@ export type DownloadOptions = {
   chapterEmbed: boolean
   compatibilityMode: boolean
   hdrMode: boolean
+  videoCodecPreference?: string[]
+  audioCodecPreference?: string[]
+  maxFps?: number | null
+  minFps?: number | null
+  prefer60fps?: boolean
+  maxVideoBitrate?: number | null
+  maxFilesizeMb?: number | null
+  reencodePreset?: string | null
+  deleteOriginal?: boolean
+  loudnessMode?: 'none' | 'loudnorm' | 'replaygain'
+  loudnessTarget?: number | null
+  musicMode?: boolean
+  metadataRules?: MetadataRule[]
+  coverCrop?: '' | 'none' | 'square' | 'letterbox'
+  coverSize?: number | null
+  coverFormat?: 'jpg' | 'png' | 'webp'
+  saveCoverFile?: boolean
+  liveMode?: 'none' | 'from_start' | 'from_now'
+  liveMaxDuration?: number | null
+  waitForVideo?: boolean
+  waitInterval?: number | null
+  jobId?: string | null
+  collisionPolicy?: 'skip' | 'overwrite' | 'rename' | 'ask'
+  resume?: boolean
+  retries?: number | null
+  fragmentRetries?: number | null
+  maxAttempts?: number | null
+  retryBackoffSeconds?: number | null
+}
+
+export type MetadataRule = {
+  kind: 'parse' | 'replace'
+  source: string
+  pattern: string
+  replacement?: string
+}
+
+export type MusicMetadataPreview = {
+  original_title: string
+  title: string
+  artist: string
+  album: string
+  album_artist: string
+  track: string
+}
+
+export type CommandPreview = {
+  command: string
+  args: string[]
+  constraints: string[]
 }
 
 export type DownloadProgress = {
   message: string
   progress?: number
   is_error: boolean
+  files: string[]
+  loudness: LoudnessResult[]
+  stopped: boolean
+  preflight_error: PreflightError | null
+  collision: CollisionReport | null
+  errors: DownloadError[]
+  attempts: AttemptRecord[]
+  i18n: LocalizedMessage | null
+}
+
+export type Locale = 'ja' | 'en'
+
+export type LocalizedMessage = {
+  key: string
+  params: Record<string, string>
+}
+
+export type MessageCatalog = {
+  locale: Locale
+  messages: Record<string, string>
+}
+
+// キーと引数からメッセージを組み立てる（カタログに無いキーはそのまま表示）
+export function formatMessage(catalog: MessageCatalog, message: LocalizedMessage): string {
+  const template = catalog.messages[message.key] ?? message.key
+  return template.replace(/\{(\w+)\}/g, (match, name) => message.params[name] ?? match)
+}
+
+export async function getLocale(): Promise<Locale> {
+  return await invoke('get_locale')
+}
+
+export async function getMessageCatalog(locale?: Locale): Promise<MessageCatalog> {
+  return await tauriInvoke('get_message_catalog', { locale: locale ?? null })
+}
+
+// コマンドが返したエラー（表示中の言語に直した文を持つ）
+export class CommandError extends Error {
+  constructor(readonly detail: LocalizedMessage, message: string) {
+    super(message)
+    this.name = 'CommandError'
+  }
+
+  toString(): string {
+    return this.message
+  }
+}
+
+function isLocalizedMessage(value: unknown): value is LocalizedMessage {
+  return typeof value === 'object' && value !== null && typeof (value as LocalizedMessage).key === 'string'
+}
+
+// 言語の設定を保存したときに読み直す
+let catalog: Promise<MessageCatalog> | null = null
+
+export function formatError(message: LocalizedMessage): Promise<string> {
+  catalog ??= getMessageCatalog()
+  return catalog.then(
+    (loaded) => formatMessage(loaded, message),
+    () => message.params.detail ?? message.key
+  )
+}
+
+// エラーはキーと引数で返るので、表示用の文にしてから投げる
+async function invoke<T>(command: string, args?: Record<string, unknown>): Promise<T> {
+  try {
+    return await tauriInvoke<T>(command, args)
+  } catch (e) {
+    if (!isLocalizedMessage(e)) {
+      throw e
+    }
+    throw new CommandError(e, await formatError(e))
+  }
+}
+
+export type AttemptRecord = {
+  job_id: string
+  attempt: number
+  max_attempts: number
+  started_at: number
+  success: boolean
+  exit_code: number | null
+  error: DownloadError | null
+  retry_in_seconds: number | null
+}
+
+export type DownloadErrorKind =
+  | 'private_video'
+  | 'members_only'
+  | 'login_required'
+  | 'geo_blocked'
+  | 'removed'
+  | 'http403'
+  | 'http429'
+  | 'ffmpeg_missing'
+  | 'unsupported_url'
+  | 'disk_full'
+  | 'extractor_outdated'
+  | 'network'
+  | 'unknown'
+
+export type DownloadError = {
+  kind: DownloadErrorKind
+  retryable: boolean
+  message: string
+  i18n: LocalizedMessage
+  raw: string
+}
+
+export type CollisionReport = {
+  policy: 'skip' | 'overwrite' | 'rename' | 'ask'
+  action: 'none' | 'skipped' | 'partially_skipped' | 'overwritten' | 'renamed' | 'ask' | 'unchecked'
+  existing: string[]
+  suffix: string | null
+}
+
+export type PreflightError =
+  | { kind: 'empty_directory' }
+  | { kind: 'create_failed'; path: string; reason: string }
+  | { kind: 'not_a_directory'; path: string }
+  | { kind: 'not_writable'; path: string; reason: string }
+  | { kind: 'insufficient_space'; path: string; estimated_bytes: number; required_bytes: number; available_bytes: number }
+
+export type PreflightReport = {
+  directory: string
+  created: boolean
+  estimated_bytes: number | null
+  required_bytes: number | null
+  available_bytes: number | null
+}
+
+export type RecordingProgress = {
+  job_id: string
+  elapsed_seconds: number
+  downloaded_bytes: number
+  message: string
+  i18n: LocalizedMessage
+}
+
+export type LoudnessResult = {
+  file: string
+  mode: 'loudnorm' | 'replaygain'
+  input_lufs: number
+  input_true_peak: number
+  target_lufs: number
+  gain_db: number
+}
+
+export type ReencodePreset = {
+  name: string
+  label: string
+  video_only: boolean
+}
+
+export type PostProcessProgress = {
+  file: string
+  preset: string
+  message: string
+  progress?: number
+  is_error: boolean
+}
+
+export type Preset = {
+  name: string
+  // URLを除くDownloadOptionsの項目（snake_case）
+  options: Record<string, any>
+  created_at: number
+  updated_at: number
+}
+
+export type PresetStore = {
+  default: string | null
+  presets: Preset[]
+}
+
+export async function listPresets(): Promise<PresetStore> {
+  return await invoke('list_presets')
+}
+
+export async function createPreset(name: string, options: DownloadOptions): Promise<Preset> {
+  return await invoke('create_preset', { name, options: toSnakeCase(options) })
+}
+
+export async function updatePreset(name: string, options: DownloadOptions): Promise<Preset> {
+  return await invoke('update_preset', { name, options: toSnakeCase(options) })
+}
+
+export async function renamePreset(name: string, newName: string): Promise<Preset> {
+  return await invoke('rename_preset', { name, newName })
+}
+
+export async function deletePreset(name: string): Promise<void> {
+  return await invoke('delete_preset', { name })
+}
+
+export async function duplicatePreset(name: string, newName?: string): Promise<Preset> {
+  return await invoke('duplicate_preset', { name, newName: newName ?? null })
+}
+
+export async function setDefaultPreset(name: string | null): Promise<PresetStore> {
+  return await invoke('set_default_preset', { name })
+}
+
+export async function exportPresets(destination: string, names?: string[]): Promise<string> {
+  return await invoke('export_presets', { destination, names: names ?? null })
+}
+
+export async function importPresets(path: string, overwrite: boolean): Promise<string[]> {
+  return await invoke('import_presets', { path, overwrite })
+}
+
+export type UrlKind = 'video' | 'short' | 'playlist' | 'channel' | 'live' | 'music' | 'other'
+
+export type ClassifiedUrl = {
+  original: string
+  normalized: string
+  kind: UrlKind
+  music: boolean
+  video_id: string | null
+  playlist_id: string | null
+  mix: boolean
+  channel: string | null
+  start_seconds: number | null
+  // trueの場合はvideo_urlとplaylist_urlのどちらをダウンロードするか選んでもらう（playlistModeで指定する）
+  ambiguous: boolean
+  video_url: string | null
+  playlist_url: string | null
+  removed_params: string[]
+}
+
+export async function classifyUrl(url: string): Promise<ClassifiedUrl> {
+  return await invoke('classify_url', { url })
+}
+
+export type BatchImportRequest = {
+  text?: string
+  filePath?: string
+  preset?: string
+  probeTitles?: boolean
+  // falseの場合は取り出した結果の確認のみ
+  enqueue?: boolean
+}
+
+export type BatchItem = {
+  url: string
+  kind: UrlKind
+  ambiguous: boolean
+  title: string | null
+  job: JobInfo | null
+  error: LocalizedMessage | null
+}
+
+export type BatchImportResult = {
+  items: BatchItem[]
+  duplicates: number
+  skipped: number
+}
+
+export async function importUrls(request: BatchImportRequest): Promise<BatchImportResult> {
+  return await invoke('import_urls', { request: toSnakeCase(request) })
 }
 
 export async function readClipboard(): Promise<string> {
   return await invoke('read_clipboard')
 }
 
+// クリップボードの監視で見つかったURL（"url-detected"イベント）。設定clipboardWatchが"enqueue"の場合はjobに追加したジョブが入る
+export type DetectedUrl = {
+  url: string
+  job: JobInfo | null
+  error: LocalizedMessage | null
+}
+
 export async function selectDirectory(defaultPath?: string): Promise<string | null> {
   return await invoke('select_directory', { defaultPath })
 }
@@ -44,8 +361,72 @@ export async function checkYtDlpInstalled(): Promise<boolean> {
   return await invoke('check_yt_dlp_installed')
 }
 
-export async function installYtDlp(): Promise<string> {
-  return await invoke('install_yt_dlp')
+export type DiagnosticItem = {
+  id: string
+  label: string
+  status: 'pass' | 'warn' | 'fail' | 'skipped'
+  detail: string
+  suggestion: string | null
+  depends_on: string | null
+}
+
+export async function runDiagnostics(options: { outputDirectory?: string; cookieFilePath?: string; proxy?: string } = {}): Promise<DiagnosticItem[]> {
+  return await invoke('run_diagnostics', {
+    outputDirectory: options.outputDirectory ?? null,
+    cookieFilePath: options.cookieFilePath ?? null,
+    proxy: options.proxy ?? null
+  })
+}
+
+export type ToolPath = {
+  name: string
+  configured: string | null
+  resolved: string
+  found: boolean
+  // 設定されたパスが存在しない場合の説明
+  error: LocalizedMessage | null
+}
+
+export type ToolPaths = {
+  yt_dlp: ToolPath
+  ffmpeg: ToolPath
+  ffprobe: ToolPath
+}
+
+export async function getToolPaths(): Promise<ToolPaths> {
+  return await invoke('get_tool_paths')
+}
+
+export type ManagedYtDlp = {
+  active_version: string | null
+  pinned_version: string | null
+  release_url: string
+  installed_versions: string[]
+  path: string | null
+}
+
+export async function installYtDlp(version?: string): Promise<string> {
+  return await invoke('install_yt_dlp', { version: version ?? null })
+}
+
+export async function getManagedYtDlp(): Promise<ManagedYtDlp> {
+  return await invoke('get_managed_yt_dlp')
+}
+
+export async function activateYtDlpVersion(version: string): Promise<void> {
+  return await invoke('activate_yt_dlp_version', { version })
+}
+
+export async function rollbackYtDlp(): Promise<string> {
+  return await invoke('rollback_yt_dlp')
+}
+
+export async function setYtDlpPinnedVersion(version: string | null): Promise<void> {
+  return await invoke('set_yt_dlp_pinned_version', { version })
+}
+
+export async function setYtDlpReleaseUrl(url: string | null): Promise<void> {
+  return await invoke('set_yt_dlp_release_url', { url })
 }
 
 export async function openDirectory(path: string): Promise<void> {
@@ -75,8 +456,185 @@ export async function executeDownload(options: DownloadOptions): Promise<Downloa
   return await invoke('execute_download', { options: snakeOptions })
 }
 
+export async function preflightDownload(options: DownloadOptions): Promise<PreflightReport> {
+  const snakeOptions = toSnakeCase(options)
+  return await invoke('preflight_download', { options: snakeOptions })
+}
+
+export async function stopRecording(jobId: string): Promise<void> {
+  return await invoke('stop_recording', { jobId })
+}
+
+export type JobStatus = 'queued' | 'running' | 'cancelling' | 'completed' | 'failed' | 'cancelled'
+
+export type JobInfo = {
+  job_id: string
+  url: string
+  format: string
+  source: string
+  status: JobStatus
+  progress: number | null
+  message: string | null
+  files: string[]
+  // 動画とプレイリストの両方を指すURL（playlist_modeで選んだ方をダウンロードする）
+  ambiguous: boolean
+  playlist_mode: boolean
+  created_at: number
+  finished_at: number | null
+}
+
+// HTTP APIのWebSocketで配信されるイベント
+export type JobEvent = {
+  job_id: string
+  event: string
+  payload: any
+}
+
+export async function enqueueDownload(options: DownloadOptions): Promise<JobInfo> {
+  const snakeOptions = toSnakeCase(options)
+  return await invoke('enqueue_download', { options: snakeOptions })
+}
+
+export async function listJobs(): Promise<JobInfo[]> {
+  return await invoke('list_jobs')
+}
+
+export async function cancelJob(jobId: string): Promise<JobInfo> {
+  return await invoke('cancel_job', { jobId })
+}
+
+// necd://リンクや2つ目の起動の引数を処理した結果（"deep-link"イベント）
+export type DeepLinkResult = {
+  link: string
+  job: JobInfo | null
+  error: LocalizedMessage | null
+}
+
+export type ApiServerStatus = {
+  enabled: boolean
+  running: boolean
+  port: number
+  token: string
+  url: string
+  error: LocalizedMessage | null
+}
+
+export async function getApiServerStatus(): Promise<ApiServerStatus> {
+  return await invoke('get_api_server_status')
+}
+
+export async function configureApiServer(enabled: boolean, port?: number): Promise<ApiServerStatus> {
+  return await invoke('configure_api_server', { enabled, port: port ?? null })
+}
+
+export async function regenerateApiToken(): Promise<ApiServerStatus> {
+  return await invoke('regenerate_api_token')
+}
+
+export type InterruptedJob = {
+  job_id: string
+  started_at: number
+  url: string
+  format: string
+  output_directory: string
+  partial_files: string[]
+}
+
+export async function listInterruptedJobs(): Promise<InterruptedJob[]> {
+  return await invoke('list_interrupted_jobs')
+}
+
+export async function resumeJob(jobId: string): Promise<DownloadProgress> {
+  return await invoke('resume_job', { jobId })
+}
+
+export async function discardJob(jobId: string): Promise<string[]> {
+  return await invoke('discard_job', { jobId })
+}
+
+export type JobLogInfo = {
+  job_id: string
+  modified_at: number
+  size_bytes: number
+}
+
+export async function listJobLogs(): Promise<JobLogInfo[]> {
+  return await invoke('list_job_logs')
+}
+
+export async function getJobLog(jobId: string): Promise<string> {
+  return await invoke('get_job_log', { jobId })
+}
+
+export async function exportLogs(destination: string, jobIds?: string[]): Promise<string> {
+  return await invoke('export_logs', { destination, jobIds: jobIds ?? null })
+}
+
+export async function previewDownloadCommand(options: DownloadOptions): Promise<CommandPreview> {
+  const snakeOptions = toSnakeCase(options)
+  return await invoke('preview_download_command', { options: snakeOptions })
+}
+
+export async function listReencodePresets(): Promise<ReencodePreset[]> {
+  return await invoke('list_reencode_presets')
+}
+
+export async function getDefaultMusicRules(): Promise<MetadataRule[]> {
+  return await invoke('get_default_music_rules')
+}
+
+export async function previewMusicMetadata(url: string, rules: MetadataRule[]): Promise<MusicMetadataPreview[]> {
+  return await invoke('preview_music_metadata', { url, rules })
+}
+
+export type MediaTags = {
+  title: string | null
+  artist: string | null
+  album: string | null
+  album_artist: string | null
+  track: string | null
+  year: string | null
+  genre: string | null
+  has_cover: boolean
+}
+
+// null/未指定は変更なし、空文字はタグの削除
+export type TagEdit = {
+  title?: string | null
+  artist?: string | null
+  album?: string | null
+  album_artist?: string | null
+  track?: string | null
+  year?: string | null
+  genre?: string | null
+  cover_path?: string | null
+  remove_cover?: boolean
+}
+
+export type TagWriteResult = {
+  file: string
+  error: LocalizedMessage | null
+}
+
+export async function readTags(path: string): Promise<MediaTags> {
+  return await invoke('read_tags', { path })
+}
+
+export async function writeTags(path: string, edit: TagEdit): Promise<void> {
+  return await invoke('write_tags', { path, edit })
+}
+
+export async function writeTagsBatch(files: string[], edit: TagEdit, autoTrackNumber: boolean): Promise<TagWriteResult[]> {
+  return await invoke('write_tags_batch', { files, edit, autoTrackNumber })
+}
+
+export async function listMediaFiles(directory: string): Promise<string[]> {
+  return await invoke('list_media_files', { directory })
+}
+
 export async function saveSettings(settings: any): Promise<void> {
-  return await invoke('save_settings', { settings })
+  await invoke('save_settings', { settings })
+  catalog = null
 }
 
 export async function loadSettings(): Promise<any> {